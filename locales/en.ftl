@@ -0,0 +1,194 @@
+## Common
+
+ok = OK
+back = Back
+remove = Remove
+search = Search
+settings = Settings
+automatic = Automatic
+language = Language
+theme = Theme
+groups = Groups
+group = Group
+group-favorites = Favorites
+group-recent = Recently watched
+group-other = Others
+channel-count = { $count ->
+    [one] { $count } channel
+   *[other] { $count } channels
+}
+channel-number = Channel { $number }
+unknown-language = unknown language
+
+## Notices
+
+notice-server-failed = Unable to start the local server: { $error }
+notice-playing = Playing { $channel } (stream { $stream }/{ $streams }): { $url }
+notice-playback-failed = Failed to play { $channel }: { $error }
+notice-no-stream = No stream available for { $channel }
+notice-health-progress = Checking streams: { $count ->
+    [one] { $count } channel checked
+   *[other] { $count } channels checked
+}
+notice-health-finished = { $alive } of { $total } channels online
+notice-nothing-playing = Nothing is playing
+notice-no-channel-number = No channel { $number }
+notice-picture-lost = The picture stopped: { $error }
+decoder-ended = the stream ended
+playlist-diff = { $added ->
+    [one] { $added } channel added
+   *[other] { $added } channels added
+}, { $removed ->
+    [one] { $removed } channel removed
+   *[other] { $removed } channels removed
+}
+playlist-diff-more = ... ({ $count } more)
+playlist-load-failed = Failed to load { $source }: { $error }
+playlist-refresh-failed = Failed to refresh the playlists, the previous ones are kept
+
+## Groups
+
+edit-channels = Edit channels
+check-streams = Check streams
+tv-mode = TV mode
+desktop-mode = Desktop mode
+advanced-settings = Advanced settings
+
+## Channels
+
+selection-start = Select
+selection-finish = Finish selecting
+export-selection = Export the selection ({ $count })
+export-selection-name = Selection
+export-list = Export the list
+export-playlist = Export the playlist...
+details = Details
+inspection-finish = Done
+hide-dead-channels = Hide offline channels
+
+## Channel details
+
+play = Play
+stop = Stop
+pin = Pin
+inspect-again = Analyze again
+probe = Analyze with ffprobe
+max-rate = Maximum bitrate (kb/s)
+variant-pinned = Pinned variant: { $rate } kb/s
+variant-capped = Bitrate capped at { $rate } kb/s
+variant-automatic = Automatic quality
+variant-audio-only = Audio / unknown
+inspecting = Analyzing the stream...
+inspection-failed = Analysis failed: { $error }
+single-variant = This stream offers a single quality
+audio-rendition = Audio { $group }: { $name } ({ $language })
+probing = Analyzing with ffprobe...
+probe-failed = ffprobe failed: { $error }
+no-probe = No technical analysis
+probe-container = Container: { $container }
+probe-video = Video: { $codec } · { $width }x{ $height }{ $frame_rate }
+probe-frame-rate = { $rate } fps
+probe-audio = Audio: { $codec } · { $language }{ $channels }
+probe-audio-channels = { $count ->
+    [one] { $count } channel
+   *[other] { $count } channels
+}
+probe-subtitles = Subtitles: { $codec } · { $language }
+
+## Parental controls
+
+unlock = Unlock
+lock = Lock
+enter-pin = Enter the parental PIN
+pin = PIN
+wrong-pin = Wrong PIN
+new-pin = New PIN
+save-pin = Save the PIN
+pin-set = Parental PIN set, an empty one removes it
+pin-unset = No parental PIN, nothing is locked
+lock-keywords = Words locking the groups, separated by commas
+parental-restricted = Unlock the parental controls to change them
+group-locked = Locked
+channel-locked = Locked
+
+## Profiles
+
+profiles = Profiles
+main-profile = Main
+choose-profile = Who is watching?
+new-profile = New profile
+add-profile = Add the profile
+remove-profile-hint = Right click on a profile to remove it with its favorites and history
+
+## Editor
+
+group-hidden = Hidden
+reset-channel = Reset
+channel-hidden = Hidden
+select-group = Select a group
+
+## Name cleanup
+
+cleanup = Name cleanup
+cleanup-enabled = Clean up the channel names
+cleanup-strip-quality = Remove the quality (HD, FHD, 4K...)
+cleanup-strip-symbols = Remove emojis and superscripts
+cleanup-preview = Preview ({ $count ->
+    [one] { $count } channel changed
+   *[other] { $count } channels changed
+})
+regex = Regular expression
+replacement = Replacement
+invalid = Invalid
+add-rule = Add a rule
+
+## Settings
+
+choose-m3u-file = Choose a M3U file...
+add-m3u-file = Add a M3U file
+playlist-url = Playlist URL
+add-url = Add a URL
+xtream-server = Xtream server
+xtream-username = Username
+xtream-password = Password
+add-xtream = Add Xtream
+no-sources = No playlist configured
+source-file = File
+source-url = URL
+source-xtream = Xtream
+source-name = Name
+source-refresh-hours = Hours
+source-epg = XMLTV guide
+source-on-startup = On startup
+prefix-groups = Prefix the groups with the playlist name
+merge-duplicates = Merge duplicates into variants
+preferred-quality = Preferred quality
+retry-failover = Fall back to the backup streams
+retry-attempts = Attempts per stream
+retry-timeout = Timeout (seconds)
+embedded-player = Play in the window
+player-volume = Volume
+player-audio-languages = Audio languages (fr,en)
+player-subtitle-languages = Subtitle languages (fr,en)
+server-enabled = Relay the streams on a local server
+server-restart = Restart
+server-error = Error: { $error }
+server-urls = Playlists: http://{ $address }:{ $port }/playlist.m3u and http://{ $address }:{ $port }/streams.m3u, guide: http://{ $address }:{ $port }/epg.xml
+hdhomerun-enabled = Emulate a HDHomeRun tuner
+hdhomerun-tuners = Tuners
+hdhomerun-groups = Exposed groups, separated by commas
+hdhomerun-favorites-only = Favorites only
+api-enabled = Web remote (/remote and /api)
+api-token = Access token (optional)
+reload-playlists = Reload the playlists
+groups-loaded = { $count ->
+    [one] { $count } group loaded
+   *[other] { $count } groups loaded
+}
+export-favorites = Export the favorites
+
+## Remote control
+
+remote-nothing-playing = Nothing playing
+remote-previous = Previous channel
+remote-next = Next channel
@@ -0,0 +1,194 @@
+## Commun
+
+ok = OK
+back = Retour
+remove = Supprimer
+search = Rechercher
+settings = Paramètres
+automatic = Automatique
+language = Langue
+theme = Thème
+groups = Groupes
+group = Groupe
+group-favorites = Favoris
+group-recent = Vues récemment
+group-other = Autres
+channel-count = { $count ->
+    [one] { $count } chaîne
+   *[other] { $count } chaînes
+}
+channel-number = Chaîne n° { $number }
+unknown-language = langue inconnue
+
+## Notifications
+
+notice-server-failed = Impossible de démarrer le serveur local : { $error }
+notice-playing = Lecture de { $channel } (flux { $stream }/{ $streams }) : { $url }
+notice-playback-failed = Échec de la lecture de { $channel } : { $error }
+notice-no-stream = Aucun flux disponible pour { $channel }
+notice-health-progress = Vérification des flux : { $count ->
+    [one] { $count } chaîne vérifiée
+   *[other] { $count } chaînes vérifiées
+}
+notice-health-finished = { $alive } chaînes en ligne sur { $total }
+notice-nothing-playing = Aucune lecture en cours
+notice-no-channel-number = Aucune chaîne n° { $number }
+notice-picture-lost = L'image s'est arrêtée : { $error }
+decoder-ended = le flux est terminé
+playlist-diff = { $added ->
+    [one] { $added } chaîne ajoutée
+   *[other] { $added } chaînes ajoutées
+}, { $removed ->
+    [one] { $removed } chaîne supprimée
+   *[other] { $removed } chaînes supprimées
+}
+playlist-diff-more = ... ({ $count } de plus)
+playlist-load-failed = Échec du chargement de { $source } : { $error }
+playlist-refresh-failed = Échec de l'actualisation des playlists, les précédentes sont conservées
+
+## Groupes
+
+edit-channels = Éditer les chaînes
+check-streams = Vérifier les flux
+tv-mode = Mode TV
+desktop-mode = Mode bureau
+advanced-settings = Paramètres avancés
+
+## Chaînes
+
+selection-start = Sélectionner
+selection-finish = Terminer la sélection
+export-selection = Exporter la sélection ({ $count })
+export-selection-name = Sélection
+export-list = Exporter la liste
+export-playlist = Exporter la playlist...
+details = Détails
+inspection-finish = Terminer
+hide-dead-channels = Masquer les chaînes hors ligne
+
+## Détails d'une chaîne
+
+play = Lire
+stop = Arrêter
+pin = Épingler
+inspect-again = Analyser à nouveau
+probe = Analyser avec ffprobe
+max-rate = Débit maximal (kb/s)
+variant-pinned = Variante épinglée : { $rate } kb/s
+variant-capped = Débit limité à { $rate } kb/s
+variant-automatic = Qualité automatique
+variant-audio-only = Audio / inconnu
+inspecting = Analyse du flux...
+inspection-failed = Analyse impossible : { $error }
+single-variant = Ce flux ne propose qu'une seule qualité
+audio-rendition = Audio { $group } : { $name } ({ $language })
+probing = Analyse avec ffprobe...
+probe-failed = ffprobe a échoué : { $error }
+no-probe = Aucune analyse technique
+probe-container = Conteneur : { $container }
+probe-video = Vidéo : { $codec } · { $width }x{ $height }{ $frame_rate }
+probe-frame-rate = { $rate } i/s
+probe-audio = Audio : { $codec } · { $language }{ $channels }
+probe-audio-channels = { $count ->
+    [one] { $count } canal
+   *[other] { $count } canaux
+}
+probe-subtitles = Sous-titres : { $codec } · { $language }
+
+## Contrôle parental
+
+unlock = Déverrouiller
+lock = Verrouiller
+enter-pin = Saisissez le code parental
+pin = Code
+wrong-pin = Code incorrect
+new-pin = Nouveau code
+save-pin = Enregistrer le code
+pin-set = Code parental défini, un code vide le supprime
+pin-unset = Aucun code parental, rien n'est verrouillé
+lock-keywords = Mots verrouillant les groupes, séparés par des virgules
+parental-restricted = Déverrouillez le contrôle parental pour le modifier
+group-locked = Verrouillé
+channel-locked = Verrouillée
+
+## Profils
+
+profiles = Profils
+main-profile = Principal
+choose-profile = Qui regarde ?
+new-profile = Nouveau profil
+add-profile = Ajouter le profil
+remove-profile-hint = Clic droit sur un profil pour le supprimer avec ses favoris et son historique
+
+## Éditeur
+
+group-hidden = Masqué
+reset-channel = Rétablir
+channel-hidden = Masquée
+select-group = Sélectionnez un groupe
+
+## Nettoyage des noms
+
+cleanup = Nettoyage des noms
+cleanup-enabled = Nettoyer les noms des chaînes
+cleanup-strip-quality = Retirer la qualité (HD, FHD, 4K...)
+cleanup-strip-symbols = Retirer les emojis et exposants
+cleanup-preview = Aperçu ({ $count ->
+    [one] { $count } chaîne modifiée
+   *[other] { $count } chaînes modifiées
+})
+regex = Expression régulière
+replacement = Remplacement
+invalid = Invalide
+add-rule = Ajouter une règle
+
+## Paramètres
+
+choose-m3u-file = Choisir un fichier M3U...
+add-m3u-file = Ajouter un fichier M3U
+playlist-url = URL de la playlist
+add-url = Ajouter une URL
+xtream-server = Serveur Xtream
+xtream-username = Utilisateur
+xtream-password = Mot de passe
+add-xtream = Ajouter Xtream
+no-sources = Aucune playlist configurée
+source-file = Fichier
+source-url = URL
+source-xtream = Xtream
+source-name = Nom
+source-refresh-hours = Heures
+source-epg = Guide XMLTV
+source-on-startup = Au démarrage
+prefix-groups = Préfixer les groupes par le nom de la playlist
+merge-duplicates = Regrouper les doublons en variantes
+preferred-quality = Qualité préférée
+retry-failover = Basculer sur les flux de secours
+retry-attempts = Tentatives par flux
+retry-timeout = Délai (secondes)
+embedded-player = Lire dans la fenêtre
+player-volume = Volume
+player-audio-languages = Langues audio (fr,en)
+player-subtitle-languages = Langues des sous-titres (fr,en)
+server-enabled = Relayer les flux sur un serveur local
+server-restart = Redémarrer
+server-error = Erreur : { $error }
+server-urls = Playlists : http://{ $address }:{ $port }/playlist.m3u et http://{ $address }:{ $port }/streams.m3u, guide : http://{ $address }:{ $port }/epg.xml
+hdhomerun-enabled = Émuler un tuner HDHomeRun
+hdhomerun-tuners = Tuners
+hdhomerun-groups = Groupes exposés, séparés par des virgules
+hdhomerun-favorites-only = Favoris uniquement
+api-enabled = Télécommande web (/remote et /api)
+api-token = Jeton d'accès (facultatif)
+reload-playlists = Recharger les playlists
+groups-loaded = { $count ->
+    [one] { $count } groupe chargé
+   *[other] { $count } groupes chargés
+}
+export-favorites = Exporter les favoris
+
+## Télécommande
+
+remote-nothing-playing = Aucune lecture
+remote-previous = Chaîne précédente
+remote-next = Chaîne suivante
@@ -0,0 +1,240 @@
+use crate::i18n::Language;
+use crate::m3u::Channel;
+use crate::mpv::{self, now_playing, play_channel};
+use crate::server::{channels, reply, same_secret, stream_id};
+use crate::state::{UserState, FAVORITES_GROUP};
+use crate::t;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tiny_http::{Method, Request, ResponseBox};
+use url::form_urlencoded;
+
+/// Phone friendly page driving the API
+const REMOTE_PAGE: &str = include_str!("remote.html");
+
+/// JSON remote control served under `/api`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApiConfig {
+    pub enabled: bool,
+    /// Required as `?token=` or a bearer token when set
+    pub token: Option<String>,
+}
+
+pub fn route(request: &Request, path: &str, query: &str, config: &ApiConfig) -> ResponseBox {
+    let params: Vec<(String, String)> = form_urlencoded::parse(query.as_bytes())
+        .into_owned()
+        .collect();
+    let param = |name: &str| {
+        params
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    };
+
+    if let Some(token) = &config.token {
+        let bearer =
+            header(request, "Authorization").map(|value| value.trim_start_matches("Bearer "));
+        if ![param("token"), bearer]
+            .into_iter()
+            .flatten()
+            .any(|given| same_secret(given, token))
+        {
+            return error(401, "Invalid token");
+        }
+    }
+
+    let get = *request.method() == Method::Get;
+    // Pages of other sites must not drive the player through the browser
+    if !get && !is_same_origin(header(request, "Origin"), header(request, "Host")) {
+        return error(403, "Cross-origin request");
+    }
+    match (get, path) {
+        (true, "/api/groups") => json_reply(groups()),
+        (true, "/api/channels") => json_reply(list_channels(param("group"), param("search"))),
+        (true, "/api/state") => json_reply(state()),
+        (false, "/api/play") => match param("id") {
+            Some(id) => play(id),
+            None => error(400, "Missing channel id"),
+        },
+        (false, path) if path.starts_with("/api/play/") => play(&path["/api/play/".len()..]),
+        (false, "/api/stop") => {
+            mpv::stop();
+            json_reply(state())
+        }
+        (false, "/api/next") => zap_reply(1),
+        (false, "/api/previous") => zap_reply(-1),
+        _ => error(404, "Not found"),
+    }
+}
+
+/// Remote page in the interface language
+pub fn remote_page() -> String {
+    let strings = json!({
+        "nothingPlaying": t!("remote-nothing-playing"),
+        "search": t!("search"),
+        "groups": t!("groups"),
+        "previous": t!("remote-previous"),
+        "stop": t!("stop"),
+        "next": t!("remote-next"),
+    });
+    REMOTE_PAGE
+        .replace("{{lang}}", Language::current().code())
+        // Kept from closing the script element
+        .replace("{{strings}}", &strings.to_string().replace("</", "<\\/"))
+}
+
+fn header<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.as_str())
+}
+
+/// Whether a request comes from the page served by this server, or from a
+/// client that is not a browser and sends no origin
+fn is_same_origin(origin: Option<&str>, host: Option<&str>) -> bool {
+    match origin {
+        Some(origin) => origin.split_once("://").map(|(_, origin)| origin) == host,
+        None => true,
+    }
+}
+
+fn json_reply(value: Value) -> ResponseBox {
+    reply(200, "application/json", value.to_string())
+}
+
+fn error(status: u16, message: &str) -> ResponseBox {
+    reply(
+        status,
+        "application/json",
+        json!({ "error": message }).to_string(),
+    )
+}
+
+fn channel_json(channel: &Channel, state: &UserState) -> Value {
+    json!({
+        "id": stream_id(channel),
+        "name": channel.name,
+        "group": channel.group,
+        "logo": channel.logo_url,
+        "favorite": state.is_favorite(channel),
+    })
+}
+
+fn groups() -> Value {
+    let mut groups: Vec<(String, usize)> = Vec::new();
+    for channel in channels().iter() {
+        match groups.iter_mut().find(|(name, _)| *name == channel.group) {
+            Some((_, count)) => *count += 1,
+            None => groups.push((channel.group.clone(), 1)),
+        }
+    }
+    let groups: Vec<Value> = groups
+        .into_iter()
+        .map(|(name, channels)| json!({ "name": name, "channels": channels }))
+        .collect();
+    Value::Array(groups)
+}
+
+fn list_channels(group: Option<&str>, search: Option<&str>) -> Value {
+    let state = UserState::load();
+    let search = search.map(|s| s.to_lowercase());
+    let channels: Vec<Value> = channels()
+        .iter()
+        .filter(|c| match group {
+            Some(FAVORITES_GROUP) => state.is_favorite(c),
+            Some(group) => c.group == group,
+            None => true,
+        })
+        .filter(|c| {
+            search
+                .as_ref()
+                .is_none_or(|search| c.name.to_lowercase().contains(search))
+        })
+        .map(|c| channel_json(c, &state))
+        .collect();
+    Value::Array(channels)
+}
+
+fn state() -> Value {
+    let state = UserState::load();
+    match now_playing() {
+        Some(now_playing) => json!({
+            "playing": true,
+            "channel": channel_json(&now_playing.channel, &state),
+            // The upstream URL may carry the credentials of the provider
+            "url": format!("/stream/{}", stream_id(&now_playing.channel)),
+            "stream": now_playing.stream,
+            "streams": now_playing.streams,
+        }),
+        None => json!({ "playing": false }),
+    }
+}
+
+fn play(id: &str) -> ResponseBox {
+    match channels().iter().find(|c| stream_id(c) == id).cloned() {
+        Some(channel) => {
+            let response = json!({ "playing": channel_json(&channel, &UserState::load()) });
+            play_channel(channel);
+            json_reply(response)
+        }
+        None => error(404, "Unknown channel"),
+    }
+}
+
+fn zap_reply(offset: isize) -> ResponseBox {
+    match zap(offset) {
+        Some(channel) => {
+            json_reply(json!({ "playing": channel_json(&channel, &UserState::load()) }))
+        }
+        None => error(409, "Nothing is playing"),
+    }
+}
+
+/// Plays the channel next to the current one in its group, returns the
+/// channel played
+pub fn zap(offset: isize) -> Option<Channel> {
+    let current = now_playing()?;
+    let group: Vec<Channel> = channels()
+        .iter()
+        .filter(|c| c.group == current.channel.group)
+        .cloned()
+        .collect();
+    if group.is_empty() {
+        return None;
+    }
+
+    let index = group
+        .iter()
+        .position(|c| c.key() == current.channel.key())
+        .map(|index| (index as isize + offset).rem_euclid(group.len() as isize) as usize)
+        .unwrap_or_default();
+    let channel = group[index].clone();
+    play_channel(channel.clone());
+    Some(channel)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remote_page_is_translated() {
+        let page = remote_page();
+        assert!(!page.contains("{{"));
+        assert!(page.contains(&format!("<html lang=\"{}\">", Language::current().code())));
+        assert!(page.contains(&format!("\"search\":\"{}\"", t!("search"))));
+    }
+
+    #[test]
+    fn control_requests_of_other_sites_are_refused() {
+        let host = Some("192.168.1.10:8080");
+        assert!(is_same_origin(Some("http://192.168.1.10:8080"), host));
+        assert!(is_same_origin(None, host));
+        assert!(!is_same_origin(Some("https://evil.test"), host));
+        assert!(!is_same_origin(Some("null"), host));
+    }
+}
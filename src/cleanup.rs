@@ -0,0 +1,185 @@
+use crate::m3u::Channel;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+
+static QUALITY: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)\b(SD|HD|FHD|UHD|4K|8K|HEVC|H\.?265)\b").unwrap());
+
+/// Countries whose code prefixes the channel names of most IPTV providers
+const COUNTRY_CODES: [&str; 32] = [
+    "AL", "AR", "AT", "AU", "BE", "BR", "CA", "CH", "DE", "DK", "ES", "FI", "FR", "GR", "IE", "IN",
+    "IT", "MA", "MX", "NL", "NO", "PK", "PL", "PT", "RO", "RU", "SE", "TN", "TR", "UK", "US",
+    "USA",
+];
+
+/// Regex replacement applied to channel names
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CleanupRule {
+    pub pattern: String,
+    pub replacement: String,
+}
+
+/// Name cleanup applied to every channel once the playlists are loaded
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CleanupConfig {
+    pub enabled: bool,
+    pub strip_quality: bool,
+    pub strip_symbols: bool,
+    pub rules: Vec<CleanupRule>,
+}
+
+impl Default for CleanupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            strip_quality: true,
+            strip_symbols: true,
+            rules: vec![CleanupRule {
+                // Country prefixes such as "FR|", "UK:" or "DE - ", limited
+                // to known codes so that names like "BFM: Paris" are kept
+                pattern: format!(r"^(?:{})\s*[|:\-]\s*", COUNTRY_CODES.join("|")),
+                replacement: String::new(),
+            }],
+        }
+    }
+}
+
+pub struct Cleaner {
+    enabled: bool,
+    strip_symbols: bool,
+    strip_quality: bool,
+    rules: Vec<(Regex, String)>,
+}
+
+impl Cleaner {
+    /// Invalid patterns are reported and skipped
+    pub fn new(config: &CleanupConfig) -> Self {
+        let rules = config
+            .rules
+            .iter()
+            .filter_map(|rule| match Regex::new(&rule.pattern) {
+                Ok(regex) => Some((regex, rule.replacement.clone())),
+                Err(e) => {
+                    eprintln!("Invalid cleanup rule {}: {}", rule.pattern, e);
+                    None
+                }
+            })
+            .collect();
+
+        Self {
+            enabled: config.enabled,
+            strip_symbols: config.strip_symbols,
+            strip_quality: config.strip_quality,
+            rules,
+        }
+    }
+
+    /// Returns the cleaned name and the quality tag stripped from it
+    pub fn clean(&self, name: &str) -> (String, Option<String>) {
+        if !self.enabled {
+            return (name.to_string(), None);
+        }
+
+        let mut name = name.to_string();
+        if self.strip_symbols {
+            name = name.chars().filter(|c| !is_decoration(*c)).collect();
+        }
+        for (regex, replacement) in &self.rules {
+            name = regex.replace_all(&name, replacement.as_str()).into_owned();
+        }
+
+        let mut quality = None;
+        if self.strip_quality {
+            quality = detect_quality(&name);
+            name = QUALITY.replace_all(&name, "").into_owned();
+        }
+
+        let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+        let name = name.trim_matches(|c: char| "|:-_".contains(c)).trim();
+        (name.to_string(), quality)
+    }
+
+    /// Cleans from the name found in the playlist, so applying the rules
+    /// again after changing them does not stack
+    pub fn apply(&self, channels: &mut [Channel]) {
+        for channel in channels {
+            let (name, quality) = self.clean(&channel.original_name);
+            if !name.is_empty() {
+                channel.name = name;
+            }
+            channel.quality = quality;
+        }
+    }
+}
+
+/// Quality tag found in a channel name, whatever the cleanup settings
+pub fn detect_quality(name: &str) -> Option<String> {
+    QUALITY.find(name).map(|m| m.as_str().to_uppercase())
+}
+
+/// Name without decorations nor quality, used to compare channels
+pub fn normalize_name(name: &str) -> String {
+    let name: String = name.chars().filter(|c| !is_decoration(*c)).collect();
+    QUALITY
+        .replace_all(&name, "")
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// Emoji, superscripts and small capitals providers use to decorate names
+fn is_decoration(c: char) -> bool {
+    matches!(c as u32,
+        0x1D00..=0x1DBF // Phonetic extensions (ᴿᴬᵂ)
+        | 0x2070..=0x209F // Superscripts and subscripts
+        | 0x2600..=0x27BF // Miscellaneous symbols and dingbats
+        | 0xFE00..=0xFE0F // Variation selectors
+        | 0x1F000..=0x1FAFF // Emoji
+    ) || c == '\u{00B2}'
+        || c == '\u{00B3}'
+        || c == '\u{00B9}'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cleaner(rules: &[(&str, &str)]) -> Cleaner {
+        let mut config = CleanupConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        config
+            .rules
+            .extend(rules.iter().map(|(pattern, replacement)| CleanupRule {
+                pattern: pattern.to_string(),
+                replacement: replacement.to_string(),
+            }));
+        Cleaner::new(&config)
+    }
+
+    #[test]
+    fn rules_apply_in_order_on_the_previous_result() {
+        let cleaner = cleaner(&[(r"\s*Plus", "+"), (r"Canal\+", "Canal+ France")]);
+        assert_eq!(
+            cleaner.clean("FR| Canal Plus HD 📺"),
+            ("Canal+ France".to_string(), Some("HD".to_string()))
+        );
+        assert_eq!(
+            cleaner.clean("BFM: Paris"),
+            ("BFM: Paris".to_string(), None)
+        );
+    }
+
+    #[test]
+    fn names_emptied_by_the_rules_are_kept() {
+        let mut channels = [Channel::fixture("Info HD", "News", "http://info.test")];
+        cleaner(&[(".*", "")]).apply(&mut channels);
+        assert_eq!(channels[0].name, "Info HD");
+        assert_eq!(channels[0].quality, None);
+    }
+}
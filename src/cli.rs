@@ -0,0 +1,77 @@
+use crate::config::Config;
+use crate::health::{check_channels, HealthResults, DEFAULT_CONCURRENCY};
+use crate::playlist::load_sources;
+use crate::server;
+use crate::state::UserState;
+use crate::store;
+
+use std::sync::Arc;
+
+/// Runs the command given on the command line, returns false when the
+/// interface should be started instead
+pub fn run(args: &[String]) -> bool {
+    match args.first().map(String::as_str) {
+        Some("check") => {
+            check(&args[1..]);
+            true
+        }
+        Some("serve") => {
+            serve();
+            true
+        }
+        Some(command) => {
+            eprintln!("Unknown command {}", command);
+            eprintln!("Usage: rustream [check [--jobs N] | serve]");
+            true
+        }
+        None => false,
+    }
+}
+
+/// Probes every visible channel and prints the result of each one
+fn check(args: &[String]) {
+    let mut jobs = DEFAULT_CONCURRENCY;
+    if let Some(index) = args.iter().position(|arg| arg == "--jobs" || arg == "-j") {
+        match args.get(index + 1).and_then(|n| n.parse().ok()) {
+            Some(n) => jobs = n,
+            None => {
+                eprintln!("--jobs expects a number");
+                return;
+            }
+        }
+    }
+
+    let config = Config::load();
+    let (groups, _) = load_sources(&config, |_| false);
+    let channels: Vec<_> = UserState::load()
+        .apply(&groups, false, false)
+        .into_iter()
+        .flat_map(|g| g.channels)
+        .collect();
+    let total = channels.len();
+
+    let checked = check_channels(channels, jobs, |channel, report| {
+        println!("{}    {}", report.summary(), channel.name);
+    });
+
+    let alive = checked.reports.values().filter(|r| r.alive).count();
+    println!("{} channels alive out of {}", alive, total);
+
+    if let Err(e) = HealthResults::record(checked.reports) {
+        eprintln!("Failed to save the stream checks: {}", e);
+    }
+}
+
+/// Runs the local server without the interface, until the process is killed
+fn serve() {
+    let config = Config::load();
+    let (groups, _) = load_sources(&config, |_| false);
+    store::publish(Arc::new(groups));
+    if let Err(e) = server::start(&config.server) {
+        eprintln!("Failed to start the server: {}", e);
+        return;
+    }
+    loop {
+        std::thread::park();
+    }
+}
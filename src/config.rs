@@ -0,0 +1,107 @@
+use crate::cleanup::CleanupConfig;
+use crate::gamepad::GamepadConfig;
+use crate::i18n::Language;
+use crate::keymap::Keymap;
+use crate::mpv::RetryPolicy;
+use crate::parental::ParentalConfig;
+use crate::playlist::PlaylistSource;
+use crate::server::ServerConfig;
+use crate::store;
+use crate::types::Result;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+const CONFIG_FILE: &str = "config.json";
+
+/// User settings persisted between sessions
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub sources: Vec<PlaylistSource>,
+    pub prefix_groups: bool,
+    pub cleanup: CleanupConfig,
+    pub merge_duplicates: bool,
+    pub preferred_quality: Option<String>,
+    pub retry: RetryPolicy,
+    pub server: ServerConfig,
+    pub keymap: Keymap,
+    pub gamepad: GamepadConfig,
+    /// Large tiles interface for televisions
+    pub tv_mode: bool,
+    /// Video shown in the window next to the current view, mpv only playing
+    /// the sound
+    pub embedded_player: bool,
+    /// Interface language, the system one when unset
+    pub language: Option<Language>,
+    /// Name of a built-in or custom theme, the system preference when unset
+    pub theme: Option<String>,
+    pub parental: ParentalConfig,
+    /// Profiles besides the main one, picked at startup when there are some
+    pub profiles: Vec<String>,
+}
+
+impl Config {
+    pub fn load() -> Self {
+        let mut config: Self = load_json(CONFIG_FILE);
+        // Sources saved before they had an identifier keep their channel keys
+        for source in config.sources.iter_mut().filter(|s| s.id.is_empty()) {
+            source.id = source.name.clone();
+        }
+        config
+    }
+
+    pub fn save(&self) -> Result<()> {
+        save_json(CONFIG_FILE, self)?;
+        store::touch();
+        Ok(())
+    }
+}
+
+/// Reads a JSON file of the config directory, falling back to the default
+/// value when it is missing or invalid
+pub fn load_json<T: DeserializeOwned + Default>(filename: &str) -> T {
+    let path = config_dir().join(filename);
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(_) => return T::default(),
+    };
+
+    serde_json::from_str(&content).unwrap_or_else(|e| {
+        eprintln!("Failed to parse {}: {}", path.display(), e);
+        T::default()
+    })
+}
+
+pub fn save_json<T: Serialize>(filename: &str, value: &T) -> Result<()> {
+    let path = config_dir().join(filename);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(value)?)?;
+    Ok(())
+}
+
+pub fn config_dir() -> PathBuf {
+    if cfg!(test) {
+        return test_dir().join("config");
+    }
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("rustream")
+}
+
+pub fn cache_dir() -> PathBuf {
+    if cfg!(test) {
+        return test_dir().join("cache");
+    }
+    dirs::cache_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("rustream")
+}
+
+/// Keeps the tests away from the files of the user
+fn test_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("rustream-tests-{}", std::process::id()))
+}
@@ -0,0 +1,128 @@
+use crate::cleanup::{detect_quality, normalize_name};
+use crate::m3u::{Channel, Variant};
+
+use std::collections::HashMap;
+
+/// Qualities from the best to the worst, used when none is preferred
+pub const QUALITIES: [&str; 6] = ["8K", "4K", "UHD", "FHD", "HD", "SD"];
+
+/// `None` for the channels that can not be told apart, such as the ones
+/// named only with symbols, which are never merged
+fn duplicate_key(channel: &Channel) -> Option<String> {
+    match channel.id.as_deref().filter(|id| !id.is_empty()) {
+        Some(id) => Some(format!("id:{}", id.to_lowercase())),
+        None => {
+            let name = normalize_name(&channel.original_name);
+            (!name.is_empty()).then(|| format!("name:{}", name))
+        }
+    }
+}
+
+fn rank(quality: Option<&str>, preferred_quality: Option<&str>) -> usize {
+    match quality {
+        Some(quality) if Some(quality) == preferred_quality => 0,
+        Some(quality) => QUALITIES
+            .iter()
+            .position(|q| *q == quality)
+            .map_or(QUALITIES.len() + 1, |p| p + 1),
+        None => QUALITIES.len() + 2,
+    }
+}
+
+/// Folds the channels sharing a tvg-id or a normalized name into the first
+/// of them, whose stream becomes the one of the preferred quality
+pub fn merge_duplicates(channels: Vec<Channel>, preferred_quality: Option<&str>) -> Vec<Channel> {
+    let mut merged: Vec<Channel> = Vec::new();
+    let mut indexes: HashMap<String, usize> = HashMap::new();
+
+    for mut channel in channels {
+        let variant = Variant {
+            name: channel.name.clone(),
+            url: channel.url.clone(),
+            quality: channel
+                .quality
+                .clone()
+                .or_else(|| detect_quality(&channel.original_name)),
+            duration: channel.duration,
+            attributes: channel.attributes.clone(),
+        };
+        let Some(key) = duplicate_key(&channel) else {
+            merged.push(channel);
+            continue;
+        };
+
+        match indexes.get(&key) {
+            Some(&index) => merged[index].variants.push(variant),
+            None => {
+                indexes.insert(key, merged.len());
+                channel.variants = vec![variant];
+                merged.push(channel);
+            }
+        }
+    }
+
+    for channel in merged.iter_mut() {
+        let mut variants = std::mem::take(&mut channel.variants);
+        if variants.len() < 2 {
+            continue;
+        }
+        variants.sort_by_key(|v| rank(v.quality.as_deref(), preferred_quality));
+
+        // The channel takes the entry of its preferred stream, its own one
+        // being kept among the variants
+        let preferred = variants.remove(0);
+        channel.url = preferred.url;
+        channel.quality = preferred.quality;
+        channel.duration = preferred.duration;
+        channel.attributes = preferred.attributes;
+        channel.variants = variants;
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channels(names: &[&str]) -> Vec<Channel> {
+        names
+            .iter()
+            .map(|name| Channel::fixture(name, "News", &format!("http://{}.test", name)))
+            .collect()
+    }
+
+    fn urls(channel: &Channel) -> Vec<&str> {
+        channel.streams().collect()
+    }
+
+    #[test]
+    fn best_quality_is_played_first() {
+        let merged = merge_duplicates(channels(&["Info SD", "Info FHD", "Info HD"]), None);
+        assert_eq!(merged.len(), 1);
+        assert_eq!(
+            urls(&merged[0]),
+            [
+                "http://Info FHD.test",
+                "http://Info HD.test",
+                "http://Info SD.test"
+            ]
+        );
+        assert_eq!(merged[0].name, "Info SD");
+
+        let merged = merge_duplicates(channels(&["Info FHD", "Info SD"]), Some("SD"));
+        assert_eq!(urls(&merged[0])[0], "http://Info SD.test");
+    }
+
+    #[test]
+    fn streams_without_quality_come_last() {
+        let merged = merge_duplicates(channels(&["Info", "Info HD", "Sport", "★"]), None);
+        assert_eq!(merged.len(), 3);
+        assert_eq!(
+            urls(&merged[0]),
+            ["http://Info HD.test", "http://Info.test"]
+        );
+        assert_eq!(merged[0].quality.as_deref(), Some("HD"));
+        assert_eq!(urls(&merged[1]), ["http://Sport.test"]);
+        assert_eq!(urls(&merged[2]), ["http://★.test"]);
+    }
+}
@@ -0,0 +1,122 @@
+use crate::events;
+use crate::mpv::find_macos_bin;
+use crate::views::ViewMessage;
+
+use iced::widget::image::Handle;
+use std::env::consts::OS;
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex};
+use which::which;
+
+/// Size of the decoded pictures, kept small as they are decoded and drawn
+/// without a GPU
+const FRAME_WIDTH: u32 = 960;
+const FRAME_HEIGHT: u32 = 540;
+const FRAME_RATE: u32 = 25;
+
+static FFMPEG_PATH: LazyLock<String> = LazyLock::new(|| {
+    if OS == "macos" && which("ffmpeg").is_err() {
+        return find_macos_bin("ffmpeg".to_string());
+    }
+    "ffmpeg".to_string()
+});
+
+/// Bumped on every start so the reader of the previous stream stops
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+static DECODER: Mutex<Option<Child>> = Mutex::new(None);
+/// Latest picture, waiting for the interface to take it
+static FRAME: Mutex<Option<Handle>> = Mutex::new(None);
+/// Set while `ViewMessage::FrameDecoded` is not handled, so that a busy
+/// interface skips pictures instead of queueing them
+static PENDING: AtomicBool = AtomicBool::new(false);
+
+/// Decodes the pictures of a stream with ffmpeg in the background, mpv only
+/// playing its sound in the embedded mode; the interface is told when the
+/// decoder stops on its own
+pub fn start(url: &str) {
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    kill_decoder();
+
+    let filter = format!(
+        "scale={0}:{1}:force_original_aspect_ratio=decrease,pad={0}:{1}:(ow-iw)/2:(oh-ih)/2",
+        FRAME_WIDTH, FRAME_HEIGHT
+    );
+    let child = Command::new(FFMPEG_PATH.as_str())
+        .args(["-loglevel", "error", "-rw_timeout", "15000000"])
+        // Read at the pace of the stream, to stay along with the sound of mpv
+        .arg("-re")
+        .args(["-i", url, "-an", "-sn", "-vf", &filter])
+        .args(["-r", &FRAME_RATE.to_string()])
+        .args(["-pix_fmt", "rgba", "-f", "rawvideo", "pipe:1"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            eprintln!("Failed to spawn ffmpeg: {}", e);
+            events::send(ViewMessage::DecoderStopped(Some(e.to_string())));
+            return;
+        }
+    };
+    let (Some(mut output), Some(errors)) = (child.stdout.take(), child.stderr.take()) else {
+        return;
+    };
+    *DECODER.lock().unwrap() = Some(child);
+
+    // Read along so that ffmpeg never blocks on a full pipe
+    let last_error = std::thread::spawn(move || {
+        BufReader::new(errors)
+            .lines()
+            .map_while(|line| line.ok())
+            .filter(|line| !line.trim().is_empty())
+            .last()
+    });
+
+    std::thread::spawn(move || {
+        let mut buffer = vec![0; (FRAME_WIDTH * FRAME_HEIGHT * 4) as usize];
+        while output.read_exact(&mut buffer).is_ok() {
+            let mut frame = FRAME.lock().unwrap();
+            if GENERATION.load(Ordering::SeqCst) != generation {
+                return;
+            }
+            *frame = Some(Handle::from_rgba(FRAME_WIDTH, FRAME_HEIGHT, buffer.clone()));
+            drop(frame);
+            if !PENDING.swap(true, Ordering::SeqCst) {
+                events::send(ViewMessage::FrameDecoded);
+            }
+        }
+
+        if GENERATION.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        kill_decoder();
+        let error = last_error.join().ok().flatten();
+        if let Some(error) = &error {
+            eprintln!("Failed to decode the pictures: {}", error);
+        }
+        events::send(ViewMessage::DecoderStopped(error));
+    });
+}
+
+pub fn stop() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    kill_decoder();
+}
+
+/// Latest picture, `None` once the playback stopped
+pub fn take_frame() -> Option<Handle> {
+    PENDING.store(false, Ordering::SeqCst);
+    FRAME.lock().unwrap().clone()
+}
+
+fn kill_decoder() {
+    if let Some(mut child) = DECODER.lock().unwrap().take() {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+    *FRAME.lock().unwrap() = None;
+}
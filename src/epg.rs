@@ -0,0 +1,349 @@
+use crate::config::Config;
+use crate::m3u::Channel;
+
+use regex::Regex;
+use std::collections::{BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{Cursor, Read};
+use std::path::PathBuf;
+use std::sync::{LazyLock, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+static CHANNEL_ATTRIBUTE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"channel\s*=\s*"([^"]*)""#).unwrap());
+static TIME_ATTRIBUTES: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(start|stop)\s*=\s*"([^"]*)""#).unwrap());
+static TITLE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<title[^>]*>([^<]*)</title>").unwrap());
+
+/// Bytes read from a guide at once
+const CHUNK_SIZE: usize = 1 << 16;
+/// Seconds before the guides are scanned again for a channel without programme
+const MISSING_PROGRAMME_RECHECK: i64 = 15 * 60;
+
+/// Programme found for each channel id, kept until it ends
+static AIRING: LazyLock<Mutex<HashMap<String, Airing>>> = LazyLock::new(Default::default);
+
+struct Airing {
+    title: Option<String>,
+    /// Unix timestamp until which the title holds
+    until: i64,
+}
+
+/// XMLTV guide of the channels, with their curated names and the programmes
+/// of the cached guides of every enabled source, read as it is sent
+pub fn curated_epg(config: &Config, channels: &[Channel]) -> impl Read + Send + 'static {
+    let mut header = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    header += "<tv generator-info-name=\"rustream\">\n";
+
+    let mut ids = BTreeSet::new();
+    for channel in channels {
+        let Some(id) = channel.id.as_ref().filter(|id| !id.is_empty()) else {
+            continue;
+        };
+        if !ids.insert(escape(id)) {
+            continue;
+        }
+        header += &format!(
+            "  <channel id=\"{}\">\n    <display-name>{}</display-name>\n",
+            escape(id),
+            escape(&channel.name)
+        );
+        if let Some(logo_url) = &channel.logo_url {
+            header += &format!("    <icon src=\"{}\"/>\n", escape(logo_url));
+        }
+        header += "  </channel>\n";
+    }
+
+    let guides: Vec<(String, PathBuf)> = config
+        .sources
+        .iter()
+        .filter(|s| s.enabled)
+        .filter_map(|source| Some((source.name.clone(), source.cached_epg_path()?)))
+        .collect();
+    let programmes = guides
+        .into_iter()
+        .filter_map(|(name, path)| {
+            File::open(path)
+                .map_err(|e| eprintln!("Failed to read the guide of {}: {}", name, e))
+                .ok()
+        })
+        .flat_map(Programmes::new)
+        .filter(move |programme| {
+            let opening = &programme[..programme.iter().position(|b| *b == b'>').unwrap_or(0)];
+            CHANNEL_ATTRIBUTE
+                .captures(&String::from_utf8_lossy(opening))
+                .is_some_and(|caps| ids.contains(&caps[1]))
+        });
+
+    Cursor::new(header)
+        .chain(ProgrammeReader {
+            programmes: Box::new(programmes),
+            buffer: Cursor::new(Vec::new()),
+        })
+        .chain(Cursor::new("</tv>\n"))
+}
+
+/// Writes the programmes one after the other, indented in the guide
+struct ProgrammeReader {
+    programmes: Box<dyn Iterator<Item = Vec<u8>> + Send>,
+    buffer: Cursor<Vec<u8>>,
+}
+
+impl Read for ProgrammeReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        loop {
+            let read = self.buffer.read(buf)?;
+            if read > 0 || buf.is_empty() {
+                return Ok(read);
+            }
+            let Some(programme) = self.programmes.next() else {
+                return Ok(0);
+            };
+            let mut element = b"  ".to_vec();
+            element.extend(programme);
+            element.push(b'\n');
+            self.buffer = Cursor::new(element);
+        }
+    }
+}
+
+/// `<programme>` elements of a guide, read a chunk at a time as guides
+/// often weigh hundreds of megabytes
+struct Programmes<R> {
+    reader: R,
+    pending: Vec<u8>,
+    /// Bytes of `pending` already scanned
+    offset: usize,
+}
+
+impl<R: Read> Programmes<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            reader,
+            pending: Vec::new(),
+            offset: 0,
+        }
+    }
+}
+
+impl<R: Read> Iterator for Programmes<R> {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        loop {
+            let rest = &self.pending[self.offset..];
+            match find(rest, b"<programme") {
+                Some(start) => {
+                    if let Some(end) = element_end(&rest[start..]) {
+                        let element = rest[start..start + end].to_vec();
+                        self.offset += start + end;
+                        return Some(element);
+                    }
+                    self.offset += start;
+                }
+                // The end of the chunk may be the beginning of a tag
+                None => {
+                    let keep = "<programme".len() - 1;
+                    self.offset = self.pending.len().saturating_sub(keep).max(self.offset);
+                }
+            }
+
+            self.pending.drain(..self.offset);
+            self.offset = 0;
+            let mut chunk = vec![0; CHUNK_SIZE];
+            match self.reader.read(&mut chunk) {
+                Ok(0) => return None,
+                Ok(read) => self.pending.extend_from_slice(&chunk[..read]),
+                Err(e) => {
+                    eprintln!("Failed to read a guide: {}", e);
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+/// Length of the element starting the bytes, `None` if it is not complete
+fn element_end(element: &[u8]) -> Option<usize> {
+    let opening_end = find(element, b">")?;
+    if element[..opening_end].ends_with(b"/") {
+        return Some(opening_end + 1);
+    }
+    Some(find(element, b"</programme>")? + "</programme>".len())
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Title of the programme the channel is airing, from the guides of the
+/// enabled sources; guides are only scanned again once it ends
+pub fn current_programme(config: &Config, channel: &Channel) -> Option<String> {
+    let id = escape(channel.id.as_ref().filter(|id| !id.is_empty())?);
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs() as i64;
+    if let Some(airing) = AIRING.lock().unwrap().get(&id).filter(|a| now < a.until) {
+        return airing.title.clone();
+    }
+
+    let airing = config
+        .sources
+        .iter()
+        .filter(|s| s.enabled)
+        .filter_map(|source| source.epg_path(false).ok().flatten())
+        .filter_map(|path| File::open(path).ok())
+        .find_map(|guide| {
+            Programmes::new(guide)
+                .find_map(|programme| airing(&String::from_utf8_lossy(&programme), &id, now))
+        })
+        .unwrap_or(Airing {
+            title: None,
+            until: now + MISSING_PROGRAMME_RECHECK,
+        });
+    let title = airing.title.clone();
+    AIRING.lock().unwrap().insert(id, airing);
+    title
+}
+
+/// The programme if it is the one of the channel airing at `now`
+fn airing(programme: &str, id: &str, now: i64) -> Option<Airing> {
+    let opening = &programme[..programme.find('>')?];
+    if CHANNEL_ATTRIBUTE.captures(opening)?[1] != *id {
+        return None;
+    }
+    let mut start = None;
+    let mut stop = None;
+    for caps in TIME_ATTRIBUTES.captures_iter(opening) {
+        let time = parse_time(&caps[2]);
+        match &caps[1] {
+            "start" => start = time,
+            _ => stop = time,
+        }
+    }
+    let until = stop?;
+    if !(start? <= now && now < until) {
+        return None;
+    }
+    Some(Airing {
+        title: Some(unescape(&TITLE.captures(programme)?[1])),
+        until,
+    })
+}
+
+/// Parses XMLTV times such as "20240131203000 +0100" to a Unix timestamp
+fn parse_time(time: &str) -> Option<i64> {
+    let (datetime, offset) = time.split_once(' ').unwrap_or((time, "+0000"));
+    let field = |range: std::ops::Range<usize>| datetime.get(range)?.parse::<i64>().ok();
+    let (year, month, day) = (field(0..4)?, field(4..6)?, field(6..8)?);
+    let (hour, minute) = (field(8..10)?, field(10..12)?);
+    let second = field(12..14).unwrap_or_default();
+
+    // Days since 1970-01-01 of a proleptic Gregorian date
+    let (year, month) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468;
+
+    let sign = if offset.starts_with('-') { -1 } else { 1 };
+    let offset = offset.trim_start_matches(['+', '-']);
+    let offset_hours: i64 = offset.get(0..2)?.parse().ok()?;
+    let offset_minutes: i64 = offset.get(2..4)?.parse().ok()?;
+
+    Some(
+        days * 86400 + hour * 3600 + minute * 60 + second
+            - sign * (offset_hours * 3600 + offset_minutes * 60),
+    )
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GUIDE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tv>
+  <channel id="arte.fr"><display-name>Arte</display-name></channel>
+  <programme start="20240131203000 +0100" stop="20240131220000 +0100" channel="arte.fr">
+    <title lang="fr">Un film</title>
+  </programme>
+  <programme start="20240131220000 +0100" stop="20240131230000 +0100" channel="tf1.fr"/>
+  <programme start="20240131220000 +0100" stop="20240131230000 +0100" channel="arte.fr">
+    <title>Documentaire</title>
+  </programme>
+</tv>
+"#;
+
+    /// Hands a few bytes at a time, so elements span several reads
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let read = self.0.len().min(buf.len()).min(7);
+            buf[..read].copy_from_slice(&self.0[..read]);
+            self.0 = &self.0[read..];
+            Ok(read)
+        }
+    }
+
+    #[test]
+    fn programmes_are_read_across_chunks() {
+        let read: Vec<String> = Programmes::new(Trickle(GUIDE.as_bytes()))
+            .map(|programme| String::from_utf8(programme).unwrap())
+            .collect();
+        assert_eq!(read.len(), 3);
+        assert!(read[0].starts_with("<programme start=\"20240131203000 +0100\""));
+        assert!(read[0].ends_with("</programme>"));
+        assert!(read[1].ends_with("/>"));
+        assert!(read[2].contains("<title>Documentaire</title>"));
+    }
+
+    #[test]
+    fn airing_programme_is_found_with_its_end() {
+        let programmes: Vec<String> = Programmes::new(GUIDE.as_bytes())
+            .map(|programme| String::from_utf8(programme).unwrap())
+            .collect();
+        let at = |now| {
+            programmes
+                .iter()
+                .find_map(|programme| airing(programme, "arte.fr", now))
+                .map(|airing| (airing.title.unwrap(), airing.until))
+        };
+        assert_eq!(at(1706729400), Some(("Un film".to_string(), 1706734800)));
+        assert_eq!(
+            at(1706734800),
+            Some(("Documentaire".to_string(), 1706738400))
+        );
+        assert_eq!(at(1706738400), None);
+    }
+
+    #[test]
+    fn parse_time_handles_offsets() {
+        assert_eq!(parse_time("20240131203000 +0100"), Some(1706729400));
+        assert_eq!(parse_time("20240131193000"), Some(1706729400));
+        assert_eq!(parse_time("garbage"), None);
+    }
+}
@@ -0,0 +1,25 @@
+use crate::views::ViewMessage;
+
+use iced::futures::channel::mpsc;
+use iced::futures::{SinkExt, Stream, StreamExt};
+use std::sync::Mutex;
+
+static LISTENER: Mutex<Option<mpsc::UnboundedSender<ViewMessage>>> = Mutex::new(None);
+
+/// Messages sent to the interface from background threads, meant for
+/// `Subscription::run`
+pub fn messages() -> impl Stream<Item = ViewMessage> {
+    iced::stream::channel(100, |mut output| async move {
+        let (sender, mut receiver) = mpsc::unbounded();
+        *LISTENER.lock().unwrap() = Some(sender);
+        while let Some(message) = receiver.next().await {
+            let _ = output.send(message).await;
+        }
+    })
+}
+
+pub fn send(message: ViewMessage) {
+    if let Some(listener) = LISTENER.lock().unwrap().as_ref() {
+        let _ = listener.unbounded_send(message);
+    }
+}
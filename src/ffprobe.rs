@@ -0,0 +1,158 @@
+use crate::config::{load_json, save_json};
+use crate::events;
+use crate::m3u::Channel;
+use crate::mpv::find_macos_bin;
+use crate::types::Result;
+use crate::views::ViewMessage;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::env::consts::OS;
+use std::process::Command;
+use std::sync::LazyLock;
+use which::which;
+
+const PROBE_FILE: &str = "ffprobe.json";
+
+static FFPROBE_PATH: LazyLock<String> = LazyLock::new(|| {
+    if OS == "macos" && which("ffprobe").is_err() {
+        return find_macos_bin("ffprobe".to_string());
+    }
+    "ffprobe".to_string()
+});
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VideoInfo {
+    pub codec: String,
+    pub width: u32,
+    pub height: u32,
+    pub frame_rate: Option<f64>,
+}
+
+/// Audio or subtitle track
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackInfo {
+    pub codec: String,
+    pub language: Option<String>,
+    /// Audio channels, none for subtitles
+    pub channels: Option<u32>,
+}
+
+/// What ffprobe found in a stream
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StreamInfo {
+    pub container: String,
+    pub video: Vec<VideoInfo>,
+    pub audio: Vec<TrackInfo>,
+    pub subtitles: Vec<TrackInfo>,
+}
+
+/// Last probe of every analysed channel, by channel key
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProbeCache {
+    pub infos: BTreeMap<String, StreamInfo>,
+}
+
+impl ProbeCache {
+    pub fn load() -> Self {
+        load_json(PROBE_FILE)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        save_json(PROBE_FILE, self)
+    }
+
+    pub fn get(&self, channel: &Channel) -> Option<&StreamInfo> {
+        self.infos.get(&channel.key())
+    }
+}
+
+pub fn probe(url: &str) -> Result<StreamInfo> {
+    let output = Command::new(FFPROBE_PATH.as_str())
+        .args([
+            "-v",
+            "error",
+            "-print_format",
+            "json",
+            "-show_format",
+            "-show_streams",
+        ])
+        // Microseconds before giving up on a stream that does not answer
+        .args(["-rw_timeout", "15000000"])
+        .arg(url)
+        .output()?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        return Err(error.trim().to_string().into());
+    }
+    let json: Value = serde_json::from_slice(&output.stdout)?;
+    Ok(parse_output(&json))
+}
+
+/// Probes the channel on a separate thread, caching the result before
+/// handing it to the interface
+pub fn probe_in_background(channel: Channel) {
+    std::thread::spawn(move || {
+        let result = probe(&channel.url).map_err(|e| e.to_string());
+        if let Ok(info) = &result {
+            let mut cache = ProbeCache::load();
+            cache.infos.insert(channel.key(), info.clone());
+            if let Err(e) = cache.save() {
+                eprintln!("Failed to save the stream info: {}", e);
+            }
+        }
+        events::send(ViewMessage::StreamProbed(channel.key(), result));
+    });
+}
+
+fn parse_output(json: &Value) -> StreamInfo {
+    let mut info = StreamInfo {
+        container: json["format"]["format_long_name"]
+            .as_str()
+            .or(json["format"]["format_name"].as_str())
+            .unwrap_or("?")
+            .to_string(),
+        video: Vec::new(),
+        audio: Vec::new(),
+        subtitles: Vec::new(),
+    };
+
+    for stream in json["streams"].as_array().into_iter().flatten() {
+        let codec = stream["codec_name"].as_str().unwrap_or("?").to_string();
+        let language = stream["tags"]["language"].as_str().map(|l| l.to_string());
+        match stream["codec_type"].as_str() {
+            Some("video") => info.video.push(VideoInfo {
+                codec,
+                width: stream["width"].as_u64().unwrap_or_default() as u32,
+                height: stream["height"].as_u64().unwrap_or_default() as u32,
+                frame_rate: stream["avg_frame_rate"]
+                    .as_str()
+                    .and_then(parse_rate)
+                    .or_else(|| stream["r_frame_rate"].as_str().and_then(parse_rate)),
+            }),
+            Some("audio") => info.audio.push(TrackInfo {
+                codec,
+                language,
+                channels: stream["channels"].as_u64().map(|c| c as u32),
+            }),
+            Some("subtitle") => info.subtitles.push(TrackInfo {
+                codec,
+                language,
+                channels: None,
+            }),
+            _ => {}
+        }
+    }
+    info
+}
+
+/// Frame rates are given as fractions such as "30000/1001"
+fn parse_rate(rate: &str) -> Option<f64> {
+    let (numerator, denominator) = rate.split_once('/')?;
+    let numerator: f64 = numerator.parse().ok()?;
+    let denominator: f64 = denominator.parse().ok()?;
+    (numerator > 0.0 && denominator > 0.0).then(|| numerator / denominator)
+}
@@ -0,0 +1,108 @@
+use crate::events;
+use crate::keymap::{keys, Keymap};
+use crate::views::ViewMessage;
+
+use gilrs::{Axis, EventType, Gilrs};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+
+/// Stick deflection counted as a press, and below which it is released
+const STICK_PRESS: f32 = 0.7;
+const STICK_RELEASE: f32 = 0.3;
+
+/// Buttons of the settings, kept up to date by the interface
+static BUTTONS: LazyLock<RwLock<Keymap>> = LazyLock::new(Default::default);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GamepadConfig {
+    pub enabled: bool,
+    /// Buttons named as in gilrs, the left stick being seen as the
+    /// `LeftStickUp`, `LeftStickDown`, `LeftStickLeft` and `LeftStickRight`
+    /// buttons
+    pub buttons: Keymap,
+}
+
+impl Default for GamepadConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            buttons: Keymap {
+                up: keys(&["DPadUp", "LeftStickUp"]),
+                down: keys(&["DPadDown", "LeftStickDown"]),
+                left: keys(&["DPadLeft", "LeftStickLeft"]),
+                right: keys(&["DPadRight", "LeftStickRight"]),
+                activate: keys(&["South"]),
+                back: keys(&["East", "Select"]),
+                search: Vec::new(),
+                next: keys(&["RightTrigger"]),
+                previous: keys(&["LeftTrigger"]),
+                play_pause: keys(&["Start"]),
+                stop: keys(&["West"]),
+            },
+        }
+    }
+}
+
+/// Reads the gamepads in the background and sends the actions of their
+/// buttons to the interface
+pub fn start(buttons: Keymap) {
+    set_buttons(buttons);
+    std::thread::spawn(|| {
+        let mut gilrs = match Gilrs::new() {
+            Ok(gilrs) => gilrs,
+            Err(e) => {
+                eprintln!("Failed to open the gamepads: {}", e);
+                return;
+            }
+        };
+
+        let mut sticks = HashMap::new();
+        loop {
+            let Some(event) = gilrs.next_event_blocking(None) else {
+                continue;
+            };
+            let button = match event.event {
+                EventType::ButtonPressed(button, _) => Some(format!("{:?}", button)),
+                EventType::AxisChanged(axis, value, _) => stick_pressed(&mut sticks, axis, value),
+                _ => None,
+            };
+            let action = button.and_then(|button| BUTTONS.read().unwrap().action_named(&button));
+            if let Some(action) = action {
+                events::send(ViewMessage::ActionTriggered(action));
+            }
+        }
+    });
+}
+
+pub fn set_buttons(buttons: Keymap) {
+    *BUTTONS.write().unwrap() = buttons;
+}
+
+/// Name of the stick direction pushed by the move, if it was released before
+fn stick_pressed(sticks: &mut HashMap<Axis, i8>, axis: Axis, value: f32) -> Option<String> {
+    let direction = if value >= STICK_PRESS {
+        1
+    } else if value <= -STICK_PRESS {
+        -1
+    } else {
+        if value.abs() < STICK_RELEASE {
+            sticks.insert(axis, 0);
+        }
+        return None;
+    };
+    if sticks.insert(axis, direction) == Some(direction) {
+        return None;
+    }
+
+    let name = match (axis, direction) {
+        (Axis::LeftStickX, 1) => "LeftStickRight",
+        (Axis::LeftStickX, _) => "LeftStickLeft",
+        // Upwards is positive
+        (Axis::LeftStickY, 1) => "LeftStickUp",
+        (Axis::LeftStickY, _) => "LeftStickDown",
+        _ => return None,
+    };
+    Some(name.to_string())
+}
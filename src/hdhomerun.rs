@@ -0,0 +1,315 @@
+use crate::config::{load_json, save_json};
+use crate::m3u::Channel;
+use crate::mpv::find_macos_bin;
+use crate::server::{
+    self, channels, content_type, header, open_upstream, reply, stream_response, Holding,
+    ServerConfig,
+};
+use crate::state::{UserState, FAVORITES_GROUP};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::env::consts::OS;
+use std::io::Read;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{LazyLock, Mutex};
+use tiny_http::ResponseBox;
+use which::which;
+
+/// Channel numbers of the lineup by channel key, kept once given so that
+/// media servers do not mix up their guides, whatever the profile
+const GUIDE_NUMBERS_FILE: &str = "guide_numbers.json";
+
+static ACTIVE_TUNERS: AtomicUsize = AtomicUsize::new(0);
+static ASSIGNING: Mutex<()> = Mutex::new(());
+static FFMPEG_PATH: LazyLock<String> = LazyLock::new(|| {
+    if OS == "macos" && which("ffmpeg").is_err() {
+        return find_macos_bin("ffmpeg".to_string());
+    }
+    "ffmpeg".to_string()
+});
+
+/// Network tuner emulation so media servers can import the channels
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HdHomeRunConfig {
+    pub enabled: bool,
+    pub device_id: String,
+    /// Streams served at once, further requests are refused
+    pub tuner_count: usize,
+    /// Groups exposed in the lineup, all of them when empty
+    pub groups: Vec<String>,
+    pub favorites_only: bool,
+    /// Converts HLS streams to MPEG-TS with ffmpeg, as media servers expect
+    pub remux_hls: bool,
+}
+
+impl Default for HdHomeRunConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            device_id: "52555354".to_string(),
+            tuner_count: 2,
+            groups: Vec::new(),
+            favorites_only: false,
+            remux_hls: true,
+        }
+    }
+}
+
+/// Seat taken on a tuner until the stream is dropped
+struct Tuner;
+
+impl Tuner {
+    fn acquire(count: usize) -> Option<Self> {
+        ACTIVE_TUNERS
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |active| {
+                (active < count).then_some(active + 1)
+            })
+            .ok()
+            .map(|_| Tuner)
+    }
+}
+
+impl Drop for Tuner {
+    fn drop(&mut self) {
+        ACTIVE_TUNERS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// ffmpeg output, the process is killed once the client is gone
+struct Remuxer(Child);
+
+impl Read for Remuxer {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self.0.stdout.as_mut() {
+            Some(stdout) => stdout.read(buf),
+            None => Ok(0),
+        }
+    }
+}
+
+impl Drop for Remuxer {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+pub fn route(path: &str, base: &str, server: &ServerConfig) -> Option<ResponseBox> {
+    let config = &server.hdhomerun;
+    let response = match path {
+        "/discover.json" | "/device.json" => json_reply(discover(base, config)),
+        "/lineup_status.json" => json_reply(json!({
+            "ScanInProgress": 0,
+            "ScanPossible": 1,
+            "Source": "Cable",
+            "SourceList": ["Cable"],
+        })),
+        "/lineup.json" => json_reply(lineup(base, config)),
+        "/lineup.post" => reply(200, "text/plain", String::new()),
+        _ => {
+            let number = path.strip_prefix("/auto/v")?;
+            return Some(tune(number, server));
+        }
+    };
+    Some(response)
+}
+
+fn json_reply(value: serde_json::Value) -> ResponseBox {
+    reply(200, "application/json", value.to_string())
+}
+
+fn discover(base: &str, config: &HdHomeRunConfig) -> serde_json::Value {
+    json!({
+        "FriendlyName": "Rustream",
+        "Manufacturer": "Silicondust",
+        "ModelNumber": "HDTC-2US",
+        "FirmwareName": "hdhomeruntc_atsc",
+        "FirmwareVersion": "20200101",
+        "DeviceID": config.device_id,
+        "DeviceAuth": "rustream",
+        "TunerCount": config.tuner_count,
+        "BaseURL": base,
+        "LineupURL": format!("{}/lineup.json", base),
+    })
+}
+
+/// Channels of the lineup with their guide number, the ones never listed
+/// before being numbered in the order shown in the interface
+pub fn lineup_channels(config: &HdHomeRunConfig) -> Vec<(usize, Channel)> {
+    // Two requests must not give the same number to different channels
+    let _assigning = ASSIGNING.lock().unwrap();
+    let state = UserState::load();
+    let channels: Vec<Channel> = channels()
+        .iter()
+        .filter(|c| !config.favorites_only || state.is_favorite(c))
+        .filter(|c| {
+            config.groups.is_empty()
+                || config.groups.contains(&c.group)
+                || (config.groups.iter().any(|g| g == FAVORITES_GROUP) && state.is_favorite(c))
+        })
+        .cloned()
+        .collect();
+
+    let mut numbers = load_json(GUIDE_NUMBERS_FILE);
+    if assign_guide_numbers(&mut numbers, &channels) {
+        if let Err(e) = save_json(GUIDE_NUMBERS_FILE, &numbers) {
+            eprintln!("Failed to save the guide numbers: {}", e);
+        }
+    }
+    let mut lineup: Vec<(usize, Channel)> = channels
+        .into_iter()
+        .map(|channel| (numbers[&channel.key()], channel))
+        .collect();
+    lineup.sort_by_key(|(number, _)| *number);
+    lineup
+}
+
+/// Numbers the channels without one after the highest number given, returns
+/// whether any was
+fn assign_guide_numbers(numbers: &mut BTreeMap<String, usize>, channels: &[Channel]) -> bool {
+    let mut next = numbers.values().max().copied().unwrap_or_default() + 1;
+    let mut assigned = false;
+    for channel in channels {
+        numbers.entry(channel.key()).or_insert_with(|| {
+            assigned = true;
+            next += 1;
+            next - 1
+        });
+    }
+    assigned
+}
+
+fn lineup(base: &str, config: &HdHomeRunConfig) -> serde_json::Value {
+    let lineup: Vec<_> = lineup_channels(config)
+        .into_iter()
+        .map(|(number, channel)| {
+            json!({
+                "GuideNumber": number.to_string(),
+                "GuideName": channel.name,
+                "URL": format!("{}/auto/v{}", base, number),
+                "HD": channel.quality.as_deref().is_some_and(|q| q != "SD") as u8,
+            })
+        })
+        .collect();
+    serde_json::Value::Array(lineup)
+}
+
+fn tune(number: &str, server: &ServerConfig) -> ResponseBox {
+    let config = &server.hdhomerun;
+    let channel = lineup_channels(config)
+        .into_iter()
+        .find(|(n, _)| n.to_string() == number)
+        .map(|(_, channel)| channel);
+    let Some(channel) = channel else {
+        return reply(404, "text/plain", "Unknown channel".to_string());
+    };
+    let Some(tuner) = Tuner::acquire(config.tuner_count) else {
+        return reply(503, "text/plain", "All tuners in use".to_string())
+            .with_header(header("X-HDHomeRun-Error", "805 All Tuners In Use"));
+    };
+
+    if config.remux_hls && channel.url.contains(".m3u8") {
+        return match remux(&channel, server) {
+            Ok(remuxer) => stream_response(
+                "video/mp2t",
+                Holding {
+                    reader: remuxer,
+                    held: tuner,
+                },
+            ),
+            Err(e) => reply(502, "text/plain", e.to_string()),
+        };
+    }
+
+    match open_upstream(&channel, &channel.url, server) {
+        Ok(response) => stream_response(
+            &content_type(&response),
+            Holding {
+                reader: response,
+                held: tuner,
+            },
+        ),
+        Err(e) => reply(502, "text/plain", e.to_string()),
+    }
+}
+
+fn remux(channel: &Channel, config: &ServerConfig) -> std::io::Result<Remuxer> {
+    let mut command = Command::new(FFMPEG_PATH.as_str());
+    command.args(["-hide_banner", "-loglevel", "error"]);
+    for (name, value) in server::upstream_headers(channel, config) {
+        match name {
+            "User-Agent" => command.args(["-user_agent", &value]),
+            _ => command.args(["-headers", &format!("{}: {}\r\n", name, value)]),
+        };
+    }
+    let child = command
+        .args(["-i", &channel.url, "-c", "copy", "-f", "mpegts", "pipe:1"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    Ok(Remuxer(child))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::tests::{fixture, get};
+    use serde_json::Value;
+
+    #[test]
+    fn guide_numbers_are_kept() {
+        let channel = |name: &str| Channel::fixture(name, "Tests", "http://127.0.0.1/stream");
+        let mut numbers = BTreeMap::new();
+        assert!(assign_guide_numbers(
+            &mut numbers,
+            &[channel("One"), channel("Two")]
+        ));
+        assert!(!assign_guide_numbers(&mut numbers, &[channel("Two")]));
+        assert!(assign_guide_numbers(
+            &mut numbers,
+            &[channel("Three"), channel("Two")]
+        ));
+        assert_eq!(numbers[&channel("One").key()], 1);
+        assert_eq!(numbers[&channel("Two").key()], 2);
+        assert_eq!(numbers[&channel("Three").key()], 3);
+    }
+
+    #[test]
+    fn discover_points_to_the_lineup() {
+        let fixture = fixture();
+        let (status, body) = get(&format!("{}/discover.json", fixture.local));
+        assert_eq!(status, 200);
+        let discover: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(discover["BaseURL"], fixture.local.as_str());
+        assert_eq!(
+            discover["LineupURL"],
+            format!("{}/lineup.json", fixture.local).as_str()
+        );
+        assert_eq!(discover["TunerCount"], 2);
+    }
+
+    #[test]
+    fn lineup_tunes_the_channels() {
+        let fixture = fixture();
+        let (status, body) = get(&format!("{}/lineup.json", fixture.local));
+        assert_eq!(status, 200);
+        let lineup: Vec<Value> = serde_json::from_str(&body).unwrap();
+        assert_eq!(lineup.len(), fixture.channels.len());
+
+        let sport = lineup
+            .iter()
+            .find(|entry| entry["GuideName"] == "Sport")
+            .unwrap();
+        let url = sport["URL"].as_str().unwrap();
+        assert!(url.starts_with(&format!("{}/auto/v", fixture.local)));
+        assert_eq!(get(url), (200, "segment".to_string()));
+
+        // Asking again gives the same numbers
+        let (_, again) = get(&format!("{}/lineup.json", fixture.local));
+        assert_eq!(again, body);
+    }
+}
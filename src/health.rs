@@ -0,0 +1,286 @@
+use crate::config::{config_dir, load_json, save_json};
+use crate::events;
+use crate::m3u::Channel;
+use crate::types::Result;
+use crate::views::ViewMessage;
+
+use reqwest::blocking::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const HEALTH_FILE: &str = "health.json";
+const LOCK_FILE: &str = "health.lock";
+const PROBE_SIZE: usize = 188 * 2;
+
+pub const DEFAULT_CONCURRENCY: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum StreamFormat {
+    MpegTs,
+    Hls,
+    Unknown,
+}
+
+/// Result of the last probe of a stream
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub alive: bool,
+    pub status: Option<u16>,
+    pub content_type: Option<String>,
+    pub format: StreamFormat,
+    /// Milliseconds before the first bytes of the stream
+    pub ttfb_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+impl HealthReport {
+    fn failed(status: Option<u16>, error: String) -> Self {
+        Self {
+            alive: false,
+            status,
+            content_type: None,
+            format: StreamFormat::Unknown,
+            ttfb_ms: None,
+            error: Some(error),
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        if let Some(error) = &self.error {
+            return format!("DEAD  {}", error);
+        }
+        format!(
+            "OK    {} {} {:?} {}ms",
+            self.status.unwrap_or_default(),
+            self.content_type.as_deref().unwrap_or("?"),
+            self.format,
+            self.ttfb_ms.unwrap_or_default()
+        )
+    }
+}
+
+/// Probe results of every checked channel, by channel key
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HealthResults {
+    pub reports: BTreeMap<String, HealthReport>,
+}
+
+impl HealthResults {
+    pub fn load() -> Self {
+        load_json(HEALTH_FILE)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        save_json(HEALTH_FILE, self)
+    }
+
+    /// Adds reports to the saved ones, a lock keeping the checks of the
+    /// interface and of the command line from losing each other's results
+    pub fn record(reports: BTreeMap<String, HealthReport>) -> Result<()> {
+        std::fs::create_dir_all(config_dir())?;
+        let lock = File::create(config_dir().join(LOCK_FILE))?;
+        lock.lock()?;
+        let mut results = Self::load();
+        results.reports.extend(reports);
+        results.save()
+    }
+
+    /// Channels never checked are not considered dead
+    pub fn is_dead(&self, channel: &Channel) -> bool {
+        self.reports
+            .get(&channel.key())
+            .is_some_and(|report| !report.alive)
+    }
+}
+
+pub fn probe(client: &Client, url: &str) -> HealthReport {
+    let started_at = Instant::now();
+    let mut response = match client.get(url).send() {
+        Ok(response) => response,
+        Err(e) => return HealthReport::failed(None, e.to_string()),
+    };
+
+    let status = response.status();
+    if !status.is_success() {
+        return HealthReport::failed(Some(status.as_u16()), format!("HTTP {}", status));
+    }
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string());
+
+    let mut buffer = vec![0; PROBE_SIZE];
+    let read = match response.read(&mut buffer) {
+        Ok(0) => return HealthReport::failed(Some(status.as_u16()), "Empty stream".to_string()),
+        Ok(read) => read,
+        Err(e) => return HealthReport::failed(Some(status.as_u16()), e.to_string()),
+    };
+    let ttfb = started_at.elapsed();
+
+    HealthReport {
+        alive: true,
+        status: Some(status.as_u16()),
+        content_type,
+        format: detect_format(&buffer[..read]),
+        ttfb_ms: Some(ttfb.as_millis() as u64),
+        error: None,
+    }
+}
+
+fn detect_format(bytes: &[u8]) -> StreamFormat {
+    if bytes.starts_with(b"#EXTM3U") {
+        StreamFormat::Hls
+    } else if bytes.first() == Some(&0x47) && bytes.get(188).is_none_or(|b| *b == 0x47) {
+        // MPEG-TS packets are 188 bytes long and start with a sync byte
+        StreamFormat::MpegTs
+    } else {
+        StreamFormat::Unknown
+    }
+}
+
+/// Probes the channels with at most `concurrency` requests at once, calling
+/// `on_report` as soon as each one is checked
+pub fn check_channels(
+    channels: Vec<Channel>,
+    concurrency: usize,
+    on_report: impl Fn(&Channel, &HealthReport) + Send + Sync + 'static,
+) -> HealthResults {
+    let client = Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+        .unwrap_or_default();
+    let queue = Arc::new(Mutex::new(channels.into_iter()));
+    let results = Arc::new(Mutex::new(HealthResults::default()));
+    let on_report = Arc::new(on_report);
+
+    let workers: Vec<_> = (0..concurrency.max(1))
+        .map(|_| {
+            let client = client.clone();
+            let queue = queue.clone();
+            let results = results.clone();
+            let on_report = on_report.clone();
+            std::thread::spawn(move || loop {
+                let Some(channel) = queue.lock().unwrap().next() else {
+                    break;
+                };
+                let report = probe(&client, &channel.url);
+                on_report(&channel, &report);
+                results
+                    .lock()
+                    .unwrap()
+                    .reports
+                    .insert(channel.key(), report);
+            })
+        })
+        .collect();
+
+    for worker in workers {
+        let _ = worker.join();
+    }
+    let results = results.lock().unwrap().clone();
+    results
+}
+
+/// Checks the channels on a separate thread, reporting each probe to the
+/// interface and saving the results once done
+pub fn check_in_background(channels: Vec<Channel>) {
+    std::thread::spawn(move || {
+        let checked = check_channels(channels, DEFAULT_CONCURRENCY, |channel, report| {
+            events::send(ViewMessage::HealthChecked(channel.key(), report.clone()));
+        });
+
+        if let Err(e) = HealthResults::record(checked.reports.clone()) {
+            eprintln!("Failed to save the stream checks: {}", e);
+        }
+
+        let alive = checked.reports.values().filter(|r| r.alive).count();
+        events::send(ViewMessage::HealthCheckFinished(
+            alive,
+            checked.reports.len(),
+        ));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tiny_http::{Response, Server};
+
+    /// Serves a stream of each kind on localhost, returning its address
+    fn fixture_server() -> String {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", server.server_addr().to_ip().unwrap());
+        std::thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let response = match request.url() {
+                    "/live.ts" => Response::from_data(vec![0x47; PROBE_SIZE]),
+                    "/live.m3u8" => Response::from_data(b"#EXTM3U\n#EXT-X-VERSION:3\n".to_vec()),
+                    "/empty" => Response::from_data(Vec::new()),
+                    _ => Response::from_data(Vec::new()).with_status_code(404),
+                };
+                let _ = request.respond(response);
+            }
+        });
+        address
+    }
+
+    #[test]
+    fn probe_detects_the_format() {
+        let address = fixture_server();
+        let client = Client::new();
+
+        let report = probe(&client, &format!("{}/live.ts", address));
+        assert!(report.alive);
+        assert_eq!(report.status, Some(200));
+        assert_eq!(report.format, StreamFormat::MpegTs);
+
+        let report = probe(&client, &format!("{}/live.m3u8", address));
+        assert!(report.alive);
+        assert_eq!(report.format, StreamFormat::Hls);
+    }
+
+    #[test]
+    fn probe_reports_dead_streams() {
+        let address = fixture_server();
+        let client = Client::new();
+
+        let report = probe(&client, &format!("{}/missing", address));
+        assert!(!report.alive);
+        assert_eq!(report.status, Some(404));
+
+        let report = probe(&client, &format!("{}/empty", address));
+        assert!(!report.alive);
+        assert_eq!(report.error.as_deref(), Some("Empty stream"));
+
+        let report = probe(&client, "http://127.0.0.1:1/closed.ts");
+        assert!(!report.alive);
+        assert_eq!(report.status, None);
+    }
+
+    #[test]
+    fn check_channels_reports_every_channel() {
+        let address = fixture_server();
+        let channels: Vec<Channel> = ["live.ts", "live.m3u8", "missing", "empty"]
+            .iter()
+            .map(|path| Channel::fixture(path, "Tests", &format!("{}/{}", address, path)))
+            .collect();
+        let reported = Arc::new(Mutex::new(0));
+        let counter = reported.clone();
+
+        let results = check_channels(channels.clone(), 2, move |_, _| {
+            *counter.lock().unwrap() += 1;
+        });
+        assert_eq!(*reported.lock().unwrap(), 4);
+        assert_eq!(results.reports.len(), 4);
+        assert!(!results.is_dead(&channels[0]));
+        assert!(!results.is_dead(&channels[1]));
+        assert!(results.is_dead(&channels[2]));
+        assert!(results.is_dead(&channels[3]));
+    }
+}
@@ -0,0 +1,305 @@
+use crate::events;
+use crate::t;
+use crate::types::Result;
+use crate::views::ViewMessage;
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Read;
+use url::Url;
+
+/// Rendition listed by an `#EXT-X-STREAM-INF` tag
+#[derive(Debug, Clone, PartialEq)]
+pub struct HlsVariant {
+    pub bandwidth: u64,
+    pub resolution: Option<(u32, u32)>,
+    pub codecs: Option<String>,
+    /// Group id of the audio renditions used with this variant
+    pub audio: Option<String>,
+    pub url: String,
+}
+
+/// Alternative audio listed by an `#EXT-X-MEDIA` tag
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioRendition {
+    pub group_id: String,
+    pub name: String,
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MasterPlaylist {
+    pub variants: Vec<HlsVariant>,
+    pub audio: Vec<AudioRendition>,
+}
+
+/// Variant the player should use for a channel
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum VariantChoice {
+    /// Always play the variant with this bandwidth
+    Pinned { bandwidth: u64 },
+    /// Let the player adapt, without going above this bandwidth
+    Capped { bandwidth: u64 },
+}
+
+impl VariantChoice {
+    /// Options telling mpv which rendition to pick, through its HLS demuxer
+    /// or yt-dlp; a pinned variant is rather played from its own URL
+    pub fn mpv_args(&self) -> Vec<String> {
+        match *self {
+            // The HLS demuxer only picks a variant when the stream is opened
+            Self::Pinned { bandwidth } => vec![format!("--hls-bitrate={}", bandwidth)],
+            Self::Capped { bandwidth } => {
+                let kbps = bandwidth.div_ceil(1000);
+                vec![
+                    format!("--hls-bitrate={}", bandwidth),
+                    format!(
+                        "--ytdl-format=bestvideo[tbr<={0}]+bestaudio/best[tbr<={0}]",
+                        kbps
+                    ),
+                ]
+            }
+        }
+    }
+
+    /// URL of the pinned variant in the master playlist behind `url`, which
+    /// the player can not switch away from; none when the variant is gone or
+    /// its audio is a separate rendition
+    pub fn pinned_url(&self, url: &str) -> Option<String> {
+        let Self::Pinned { bandwidth } = *self else {
+            return None;
+        };
+        let master = fetch_master(url)
+            .map_err(|e| eprintln!("Failed to read the variants of {}: {}", url, e))
+            .ok()?;
+        master
+            .variants
+            .into_iter()
+            .find(|variant| variant.bandwidth == bandwidth && variant.audio.is_none())
+            .map(|variant| variant.url)
+    }
+
+    /// URL of the variant the embedded mode plays, the pinned one or the best
+    /// one under the cap, so that its two players read the same segments
+    pub fn variant_url(&self, url: &str) -> Option<String> {
+        let Self::Capped { bandwidth } = *self else {
+            return self.pinned_url(url);
+        };
+        let master = fetch_master(url)
+            .map_err(|e| eprintln!("Failed to read the variants of {}: {}", url, e))
+            .ok()?;
+        master
+            .variants
+            .into_iter()
+            .filter(|variant| variant.bandwidth <= bandwidth && variant.audio.is_none())
+            .max_by_key(|variant| variant.bandwidth)
+            .map(|variant| variant.url)
+    }
+}
+
+impl HlsVariant {
+    pub fn label(&self) -> String {
+        let mut label = match self.resolution {
+            Some((width, height)) => format!("{}x{}", width, height),
+            None => t!("variant-audio-only"),
+        };
+        label.push_str(&format!(
+            " · {:.1} Mb/s",
+            self.bandwidth as f64 / 1_000_000.0
+        ));
+        if let Some(codecs) = &self.codecs {
+            label.push_str(&format!(" · {}", codecs));
+        }
+        if let Some(audio) = &self.audio {
+            label.push_str(&format!(" · audio {}", audio));
+        }
+        label
+    }
+}
+
+/// Downloads the playlist behind a stream URL, a media playlist or any other
+/// stream gives an empty master playlist
+pub fn fetch_master(url: &str) -> Result<MasterPlaylist> {
+    let mut response = reqwest::blocking::get(url)?.error_for_status()?;
+    let base = response.url().clone();
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_lowercase();
+    if content_type.starts_with("video/") && !content_type.contains("mpegurl") {
+        return Ok(MasterPlaylist::default());
+    }
+
+    // Live streams never end, only a playlist is worth reading further
+    let mut content = Vec::new();
+    (&mut response).take(7).read_to_end(&mut content)?;
+    if !content.starts_with(b"#EXTM3U") {
+        return Ok(MasterPlaylist::default());
+    }
+    response.take(1 << 20).read_to_end(&mut content)?;
+    Ok(parse_master(&base, &String::from_utf8_lossy(&content)))
+}
+
+/// Fetches the master playlist on a separate thread and hands it to the
+/// interface
+pub fn inspect_in_background(url: String) {
+    std::thread::spawn(move || {
+        let result = fetch_master(&url).map_err(|e| e.to_string());
+        events::send(ViewMessage::HlsInspected(url, result));
+    });
+}
+
+pub fn parse_master(base: &Url, content: &str) -> MasterPlaylist {
+    let mut playlist = MasterPlaylist::default();
+    let mut pending: Option<BTreeMap<String, String>> = None;
+
+    for line in content.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        if let Some(attributes) = line.strip_prefix("#EXT-X-STREAM-INF:") {
+            pending = Some(parse_attributes(attributes));
+        } else if let Some(attributes) = line.strip_prefix("#EXT-X-MEDIA:") {
+            let attributes = parse_attributes(attributes);
+            if attributes.get("TYPE").map(String::as_str) != Some("AUDIO") {
+                continue;
+            }
+            playlist.audio.push(AudioRendition {
+                group_id: attributes.get("GROUP-ID").cloned().unwrap_or_default(),
+                name: attributes.get("NAME").cloned().unwrap_or_default(),
+                language: attributes.get("LANGUAGE").cloned(),
+            });
+        } else if !line.starts_with('#') {
+            let Some(attributes) = pending.take() else {
+                continue;
+            };
+            let url = base
+                .join(line)
+                .map(|u| u.to_string())
+                .unwrap_or_else(|_| line.to_string());
+            playlist.variants.push(HlsVariant {
+                bandwidth: attributes
+                    .get("BANDWIDTH")
+                    .and_then(|b| b.parse().ok())
+                    .unwrap_or_default(),
+                resolution: attributes.get("RESOLUTION").and_then(|r| {
+                    let (width, height) = r.split_once('x')?;
+                    Some((width.parse().ok()?, height.parse().ok()?))
+                }),
+                codecs: attributes.get("CODECS").cloned(),
+                audio: attributes.get("AUDIO").cloned(),
+                url,
+            });
+        }
+    }
+
+    playlist
+        .variants
+        .sort_by_key(|v| std::cmp::Reverse(v.bandwidth));
+    playlist
+}
+
+/// Parses `KEY=value,KEY="quoted, value"` attribute lists
+fn parse_attributes(list: &str) -> BTreeMap<String, String> {
+    let mut attributes = BTreeMap::new();
+    let mut rest = list;
+
+    while let Some((key, value)) = rest.split_once('=') {
+        let (value, next) = match value.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find('"').unwrap_or(quoted.len());
+                let next = quoted[end..].trim_start_matches('"');
+                (&quoted[..end], next.strip_prefix(',').unwrap_or(next))
+            }
+            None => value.split_once(',').unwrap_or((value, "")),
+        };
+        attributes.insert(key.trim().to_string(), value.to_string());
+        rest = next;
+    }
+    attributes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tiny_http::{Response, Server};
+
+    const MASTER: &str = "#EXTM3U
+#EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"Français\",LANGUAGE=\"fr\"
+#EXT-X-STREAM-INF:BANDWIDTH=800000,RESOLUTION=640x360,CODECS=\"avc1.4d401e,mp4a.40.2\"
+low/index.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=5000000,RESOLUTION=1920x1080
+high/index.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=2500000,RESOLUTION=1280x720,AUDIO=\"aac\"
+mid/index.m3u8
+";
+
+    fn master_server() -> String {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", server.server_addr().to_ip().unwrap());
+        std::thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let _ = request.respond(Response::from_string(MASTER));
+            }
+        });
+        address
+    }
+
+    #[test]
+    fn parse_master_sorts_the_variants() {
+        let base = Url::parse("http://example.com/live/master.m3u8").unwrap();
+        let playlist = parse_master(&base, MASTER);
+        let bandwidths: Vec<u64> = playlist.variants.iter().map(|v| v.bandwidth).collect();
+        assert_eq!(bandwidths, [5000000, 2500000, 800000]);
+        assert_eq!(
+            playlist.variants[2].url,
+            "http://example.com/live/low/index.m3u8"
+        );
+        assert_eq!(playlist.variants[2].resolution, Some((640, 360)));
+        assert_eq!(playlist.audio[0].language.as_deref(), Some("fr"));
+    }
+
+    #[test]
+    fn only_capped_choices_cap_the_bitrate() {
+        let pinned = VariantChoice::Pinned { bandwidth: 800000 }.mpv_args();
+        assert_eq!(pinned, ["--hls-bitrate=800000"]);
+        let capped = VariantChoice::Capped { bandwidth: 800000 }.mpv_args();
+        assert!(capped.iter().any(|arg| arg.contains("tbr<=800")));
+    }
+
+    #[test]
+    fn pinned_choice_plays_the_variant_url() {
+        let address = master_server();
+        let url = format!("{}/live/master.m3u8", address);
+
+        let pinned = VariantChoice::Pinned { bandwidth: 800000 };
+        assert_eq!(
+            pinned.pinned_url(&url),
+            Some(format!("{}/live/low/index.m3u8", address))
+        );
+        // Its audio would be lost without the master playlist
+        let separate_audio = VariantChoice::Pinned { bandwidth: 2500000 };
+        assert_eq!(separate_audio.pinned_url(&url), None);
+        let capped = VariantChoice::Capped { bandwidth: 800000 };
+        assert_eq!(capped.pinned_url(&url), None);
+    }
+
+    #[test]
+    fn embedded_mode_plays_the_best_variant_under_the_cap() {
+        let address = master_server();
+        let url = format!("{}/live/master.m3u8", address);
+
+        let capped = VariantChoice::Capped { bandwidth: 3000000 };
+        assert_eq!(
+            capped.variant_url(&url),
+            Some(format!("{}/live/low/index.m3u8", address))
+        );
+        let uncapped = VariantChoice::Capped {
+            bandwidth: u64::MAX,
+        };
+        assert_eq!(
+            uncapped.variant_url(&url),
+            Some(format!("{}/live/high/index.m3u8", address))
+        );
+    }
+}
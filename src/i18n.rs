@@ -0,0 +1,129 @@
+use crate::config::Config;
+use crate::state::{FAVORITES_GROUP, RECENT_GROUP};
+
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::{LazyLock, RwLock};
+use unic_langid::LanguageIdentifier;
+
+const ENGLISH: &str = include_str!("../locales/en.ftl");
+const FRENCH: &str = include_str!("../locales/fr.ftl");
+
+/// Messages of the interface language, English filling the missing ones
+static BUNDLE: LazyLock<RwLock<FluentBundle<FluentResource>>> =
+    LazyLock::new(|| RwLock::new(bundle(Config::load().language)));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Language {
+    English,
+    French,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::French];
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::French => "fr",
+        }
+    }
+
+    /// Language of the interface, as chosen in the settings
+    pub fn current() -> Self {
+        Config::load().language.unwrap_or_else(Language::detect)
+    }
+
+    /// Language of the system locale, English when it is not translated
+    pub fn detect() -> Self {
+        match sys_locale::get_locale() {
+            Some(locale) if locale.starts_with("fr") => Language::French,
+            _ => Language::English,
+        }
+    }
+
+    fn catalog(&self) -> &'static str {
+        match self {
+            Language::English => ENGLISH,
+            Language::French => FRENCH,
+        }
+    }
+}
+
+/// Named in the language itself, to be found whatever the current one is
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Language::English => write!(f, "English"),
+            Language::French => write!(f, "Français"),
+        }
+    }
+}
+
+fn resource(catalog: &str) -> FluentResource {
+    FluentResource::try_new(catalog.to_string()).unwrap_or_else(|(resource, errors)| {
+        eprintln!("Failed to parse the translations: {:?}", errors);
+        resource
+    })
+}
+
+fn bundle(language: Option<Language>) -> FluentBundle<FluentResource> {
+    let language = language.unwrap_or_else(Language::detect);
+    let langid: LanguageIdentifier = language.code().parse().unwrap_or_default();
+    let mut bundle = FluentBundle::new_concurrent(vec![langid]);
+    // Isolation marks are shown as boxes by the interface fonts
+    bundle.set_use_isolating(false);
+    bundle.add_resource_overriding(resource(ENGLISH));
+    if language != Language::English {
+        bundle.add_resource_overriding(resource(language.catalog()));
+    }
+    bundle
+}
+
+/// Changes the interface language, `None` following the system locale
+pub fn set_language(language: Option<Language>) {
+    *BUNDLE.write().unwrap() = bundle(language);
+}
+
+/// Message of the catalogs, its identifier when it is missing
+pub fn translate(id: &str, args: Option<&FluentArgs>) -> String {
+    let bundle = BUNDLE.read().unwrap();
+    let Some(pattern) = bundle.get_message(id).and_then(|message| message.value()) else {
+        eprintln!("Failed to find the translation of {}", id);
+        return id.to_string();
+    };
+
+    let mut errors = Vec::new();
+    let message = bundle
+        .format_pattern(pattern, args, &mut errors)
+        .to_string();
+    if !errors.is_empty() {
+        eprintln!("Failed to translate {}: {:?}", id, errors);
+    }
+    message
+}
+
+/// Name of a group as shown, the ones made by the application being
+/// translated
+pub fn group_name(name: &str) -> String {
+    match name {
+        FAVORITES_GROUP => translate("group-favorites", None),
+        RECENT_GROUP => translate("group-recent", None),
+        name => name.to_string(),
+    }
+}
+
+/// Translated message: `t!("id")` or `t!("id", name = value, ...)`
+#[macro_export]
+macro_rules! t {
+    ($id:expr) => {
+        $crate::i18n::translate($id, None)
+    };
+    ($id:expr, $($name:ident = $value:expr),+ $(,)?) => {{
+        let mut args = fluent_bundle::FluentArgs::new();
+        $(args.set(stringify!($name), $value);)+
+        $crate::i18n::translate($id, Some(&args))
+    }};
+}
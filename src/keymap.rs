@@ -0,0 +1,104 @@
+use crate::views::ViewMessage;
+
+use iced::keyboard::{Key, Modifiers};
+use serde::{Deserialize, Serialize};
+
+/// Navigation performed from the keyboard or a gamepad
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    /// Opens the focused group or plays the focused channel
+    Activate,
+    Back,
+    Search,
+    /// Plays the next channel of the group being played
+    Next,
+    Previous,
+    PlayPause,
+    Stop,
+}
+
+/// Keys bound to each action, named as in iced (`ArrowUp`, `Enter`,
+/// `Escape`...) or by the character they type, or gamepad buttons named as in
+/// gilrs (`DPadUp`, `South`...)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Keymap {
+    pub up: Vec<String>,
+    pub down: Vec<String>,
+    pub left: Vec<String>,
+    pub right: Vec<String>,
+    pub activate: Vec<String>,
+    pub back: Vec<String>,
+    pub search: Vec<String>,
+    pub next: Vec<String>,
+    pub previous: Vec<String>,
+    pub play_pause: Vec<String>,
+    pub stop: Vec<String>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self {
+            up: keys(&["ArrowUp"]),
+            down: keys(&["ArrowDown"]),
+            left: keys(&["ArrowLeft"]),
+            right: keys(&["ArrowRight"]),
+            activate: keys(&["Enter"]),
+            back: keys(&["Backspace", "Escape", "BrowserBack", "GoBack"]),
+            search: keys(&["/"]),
+            next: keys(&["PageDown", "ChannelUp", "MediaTrackNext"]),
+            previous: keys(&["PageUp", "ChannelDown", "MediaTrackPrevious"]),
+            play_pause: keys(&["Space", "MediaPlayPause"]),
+            stop: keys(&["MediaStop"]),
+        }
+    }
+}
+
+impl Keymap {
+    pub fn action(&self, key: &Key) -> Option<Action> {
+        self.action_named(&key_name(key)?)
+    }
+
+    pub fn action_named(&self, name: &str) -> Option<Action> {
+        let bindings = [
+            (&self.up, Action::Up),
+            (&self.down, Action::Down),
+            (&self.left, Action::Left),
+            (&self.right, Action::Right),
+            (&self.activate, Action::Activate),
+            (&self.back, Action::Back),
+            (&self.search, Action::Search),
+            (&self.next, Action::Next),
+            (&self.previous, Action::Previous),
+            (&self.play_pause, Action::PlayPause),
+            (&self.stop, Action::Stop),
+        ];
+        bindings
+            .into_iter()
+            .find(|(keys, _)| keys.iter().any(|key| key == name))
+            .map(|(_, action)| action)
+    }
+}
+
+pub fn keys(keys: &[&str]) -> Vec<String> {
+    keys.iter().map(|k| k.to_string()).collect()
+}
+
+fn key_name(key: &Key) -> Option<String> {
+    match key {
+        Key::Named(named) => Some(format!("{:?}", named)),
+        Key::Character(c) => Some(c.to_string()),
+        Key::Unidentified => None,
+    }
+}
+
+/// Keys pressed outside of text inputs, meant for `keyboard::on_key_press`;
+/// shortcuts with modifiers are left to the system
+pub fn key_pressed(key: Key, modifiers: Modifiers) -> Option<ViewMessage> {
+    (!modifiers.control() && !modifiers.alt() && !modifiers.logo())
+        .then_some(ViewMessage::KeyPressed(key))
+}
@@ -0,0 +1,65 @@
+use crate::config::cache_dir;
+use crate::events;
+use crate::server::hash;
+use crate::types::Result;
+use crate::views::ViewMessage;
+
+use iced::widget::image::Handle;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, LazyLock, Mutex};
+
+const WORKERS: usize = 4;
+
+/// Logos requested since the start, downloaded or not
+static REQUESTED: LazyLock<Mutex<HashSet<String>>> = LazyLock::new(Default::default);
+static QUEUE: LazyLock<Mutex<Sender<String>>> = LazyLock::new(|| {
+    let (sender, receiver) = mpsc::channel();
+    let receiver = Arc::new(Mutex::new(receiver));
+    for _ in 0..WORKERS {
+        let receiver = receiver.clone();
+        std::thread::spawn(move || download_logos(receiver));
+    }
+    Mutex::new(sender)
+});
+
+fn logo_path(url: &str) -> PathBuf {
+    cache_dir().join("logos").join(hash(url))
+}
+
+/// Image of a channel logo once it is in the cache, it is downloaded in the
+/// background otherwise and `ViewMessage::LogoLoaded` is sent when it is
+pub fn logo(url: &str) -> Option<Handle> {
+    let path = logo_path(url);
+    if path.exists() {
+        return Some(Handle::from_path(path));
+    }
+    if REQUESTED.lock().unwrap().insert(url.to_string()) {
+        let _ = QUEUE.lock().unwrap().send(url.to_string());
+    }
+    None
+}
+
+fn download_logos(receiver: Arc<Mutex<Receiver<String>>>) {
+    loop {
+        let url = match receiver.lock().unwrap().recv() {
+            Ok(url) => url,
+            Err(_) => return,
+        };
+        match download(&url) {
+            Ok(()) => events::send(ViewMessage::LogoLoaded),
+            Err(e) => eprintln!("Failed to download the logo {}: {}", url, e),
+        }
+    }
+}
+
+fn download(url: &str) -> Result<()> {
+    let content = reqwest::blocking::get(url)?.error_for_status()?.bytes()?;
+    let path = logo_path(url);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, content)?;
+    Ok(())
+}
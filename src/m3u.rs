@@ -2,19 +2,85 @@ extern crate m3u;
 use m3u::iptv::IptvEntry;
 use m3u::Reader;
 
+use crate::t;
 use crate::types::Result;
+use std::collections::BTreeMap;
 
 pub trait Named {
     fn name(&self) -> &str;
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Channel {
     pub name: String,
+    /// Name found in the playlist, kept when the user renames the channel
+    pub original_name: String,
     pub url: String,
     pub id: Option<String>,
     pub logo_url: Option<String>,
     pub group: String,
+    pub source: String,
+    /// Identifier of the source, kept when it is renamed
+    pub source_id: String,
+    /// Number of the channels of the same source and name listed before
+    /// this one, telling them apart
+    pub duplicate: usize,
+    /// Quality tag stripped from the name by the cleanup rules
+    pub quality: Option<String>,
+    /// Duration of the `#EXTINF` line, -1 for live streams
+    pub duration: f64,
+    /// Every `#EXTINF` attribute, including the ones mirrored above
+    pub attributes: BTreeMap<String, String>,
+    /// Other streams of the same channel, folded by the duplicate detection
+    pub variants: Vec<Variant>,
+}
+
+/// Stream of a folded duplicate, with the entry it came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variant {
+    pub name: String,
+    pub url: String,
+    pub quality: Option<String>,
+    pub duration: f64,
+    pub attributes: BTreeMap<String, String>,
+}
+
+impl Channel {
+    /// Identifies the channel across downloads of its playlist, where the
+    /// stream URL may change
+    pub fn key(&self) -> String {
+        match self.duplicate {
+            0 => format!("{}/{}", self.source_id, self.original_name),
+            n => format!("{}/{}#{}", self.source_id, self.original_name, n + 1),
+        }
+    }
+
+    /// Stream URLs to try in order, starting with the preferred one
+    pub fn streams(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.url.as_str()).chain(self.variants.iter().map(|v| v.url.as_str()))
+    }
+}
+
+#[cfg(test)]
+impl Channel {
+    /// Channel as the parser reads it from a playlist entry
+    pub fn fixture(name: &str, group: &str, url: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            original_name: name.to_string(),
+            url: url.to_string(),
+            id: None,
+            logo_url: None,
+            group: group.to_string(),
+            source: "test".to_string(),
+            source_id: "test".to_string(),
+            duplicate: 0,
+            quality: None,
+            duration: -1.0,
+            attributes: BTreeMap::from([("group-title".to_string(), group.to_string())]),
+            variants: Vec::new(),
+        }
+    }
 }
 
 impl Named for Channel {
@@ -35,6 +101,12 @@ impl Named for Group {
     }
 }
 
+impl Named for String {
+    fn name(&self) -> &str {
+        self
+    }
+}
+
 pub fn parse_m3u(m3u_filepath: &str) -> Result<Vec<Group>> {
     let mut reader: Reader<std::io::BufReader<std::fs::File>, IptvEntry> =
         Reader::open_iptv(m3u_filepath)?;
@@ -56,18 +128,34 @@ pub fn parse_m3u(m3u_filepath: &str) -> Result<Vec<Group>> {
             let group = props
                 .get("group-title")
                 .map(|s| s.to_string())
-                .unwrap_or_else(|| "Autres".to_string());
+                .unwrap_or_else(|| t!("group-other"));
+            let attributes = props
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect();
 
             Channel {
                 name: name.clone(),
+                original_name: name.clone(),
                 url,
                 id,
                 logo_url,
                 group: group,
+                source: String::new(),
+                source_id: String::new(),
+                duplicate: 0,
+                quality: None,
+                duration: extinf.duration,
+                attributes,
+                variants: Vec::new(),
             }
         })
         .collect();
 
+    Ok(group_channels(channels))
+}
+
+pub fn group_channels(channels: Vec<Channel>) -> Vec<Group> {
     let mut groups: Vec<Group> = Vec::new();
 
     for channel in channels {
@@ -85,5 +173,133 @@ pub fn parse_m3u(m3u_filepath: &str) -> Result<Vec<Group>> {
         }
     }
 
-    Ok(groups)
+    groups
+}
+
+fn extinf(duration: f64, attributes: &BTreeMap<String, String>, name: &str, url: &str) -> String {
+    let mut extinf = format!("#EXTINF:{}", duration);
+    for (key, value) in attributes {
+        extinf += &format!(" {}=\"{}\"", key, value);
+    }
+    format!("{},{}\n{}\n", extinf, name, url)
+}
+
+/// Sets an attribute to the field it was parsed into, if the field was
+/// edited since; M3U has no escape for the quotes of an edited value
+fn write_back(attributes: &mut BTreeMap<String, String>, key: &str, value: Option<&String>) {
+    if attributes.get(key) == value {
+        return;
+    }
+    match value {
+        Some(value) => attributes.insert(key.to_string(), value.replace('"', "'")),
+        None => attributes.remove(key),
+    };
+}
+
+/// Serializes channels back to an extended M3U playlist, their entries being
+/// written as they were parsed apart from the edited fields; variants are
+/// written as the entries they were folded from
+pub fn to_m3u(channels: &[Channel]) -> String {
+    let mut content = String::from("#EXTM3U\n");
+
+    for channel in channels {
+        let mut attributes = channel.attributes.clone();
+        write_back(&mut attributes, "tvg-id", channel.id.as_ref());
+        write_back(&mut attributes, "tvg-logo", channel.logo_url.as_ref());
+        // Channels without a group were put in the default one by the parser
+        if attributes.contains_key("group-title") || channel.group != t!("group-other") {
+            write_back(&mut attributes, "group-title", Some(&channel.group));
+        }
+
+        content += &extinf(channel.duration, &attributes, &channel.name, &channel.url);
+        for variant in &channel.variants {
+            content += &extinf(
+                variant.duration,
+                &variant.attributes,
+                &variant.name,
+                &variant.url,
+            );
+        }
+    }
+    content
+}
+
+pub fn write_m3u(m3u_filepath: &str, channels: &[Channel]) -> Result<()> {
+    std::fs::write(m3u_filepath, to_m3u(channels))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/playlist.m3u");
+
+    fn channels(groups: Vec<Group>) -> Vec<Channel> {
+        groups.into_iter().flat_map(|g| g.channels).collect()
+    }
+
+    fn round_trip(channels: &[Channel], name: &str) -> Vec<Channel> {
+        let path =
+            std::env::temp_dir().join(format!("rustream-{}-{}.m3u", name, std::process::id()));
+        write_m3u(&path.to_string_lossy(), channels).unwrap();
+        let parsed = parse_m3u(&path.to_string_lossy()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        self::channels(parsed)
+    }
+
+    #[test]
+    fn round_trip_keeps_the_channels() {
+        let parsed = channels(parse_m3u(FIXTURE).unwrap());
+        assert_eq!(parsed.len(), 5);
+        assert_eq!(round_trip(&parsed, "unchanged"), parsed);
+    }
+
+    #[test]
+    fn round_trip_keeps_durations_and_attributes() {
+        let parsed = channels(parse_m3u(FIXTURE).unwrap());
+        let written = to_m3u(&parsed);
+        assert!(written.contains("#EXTINF:5400 "));
+        assert!(written.contains("#EXTINF:0 "));
+        assert!(written.contains("catchup-days=\"7\""));
+        assert!(written.contains("#EXTINF:-1,Sans groupe\n"));
+    }
+
+    #[test]
+    fn edited_group_is_written() {
+        let mut parsed = channels(parse_m3u(FIXTURE).unwrap());
+        parsed[3].group = "Divers".to_string();
+        let written = round_trip(&parsed, "edited");
+        assert_eq!(written[3].group, "Divers");
+        assert_eq!(written[3].attributes.get("group-title").unwrap(), "Divers");
+        assert_eq!(written[0], parsed[0]);
+    }
+
+    #[test]
+    fn keys_survive_renames_and_tell_duplicates_apart() {
+        let first = Channel::fixture("Info", "News", "http://info.test/1");
+        let mut renamed = first.clone();
+        renamed.source = "Renamed".to_string();
+        assert_eq!(renamed.key(), first.key());
+
+        let mut second = Channel::fixture("Info", "News", "http://info.test/2");
+        second.duplicate = 1;
+        assert_ne!(second.key(), first.key());
+    }
+
+    #[test]
+    fn variants_are_written_as_their_entries() {
+        let mut parsed = channels(parse_m3u(FIXTURE).unwrap());
+        let arte = parsed.remove(1);
+        parsed[0].variants.push(Variant {
+            name: arte.name.clone(),
+            url: arte.url.clone(),
+            quality: None,
+            duration: arte.duration,
+            attributes: arte.attributes.clone(),
+        });
+        let written = round_trip(&parsed, "variants");
+        assert_eq!(written.len(), 5);
+        assert_eq!(written[1], arte);
+    }
 }
@@ -1,16 +1,46 @@
+pub mod api;
+pub mod cleanup;
+pub mod cli;
+pub mod config;
+pub mod duplicates;
+pub mod embedded;
+pub mod epg;
+pub mod events;
+pub mod ffprobe;
+pub mod gamepad;
+pub mod hdhomerun;
+pub mod health;
+pub mod hls;
+pub mod i18n;
+pub mod keymap;
+pub mod logos;
 pub mod m3u;
+#[cfg(target_os = "linux")]
+pub mod mpris;
 pub mod mpv;
+pub mod parental;
+pub mod playlist;
+pub mod profile;
+pub mod relay;
+pub mod server;
+pub mod state;
+pub mod store;
+pub mod theme;
 pub mod types;
 pub mod ui;
 pub mod views;
 
 use crate::ui::App;
 
-use iced::Theme;
-
 fn main() -> iced::Result {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if cli::run(&args) {
+        return Ok(());
+    }
+
     iced::application("Rustream", App::update, App::view)
-        .theme(|_| Theme::Dark)
+        .subscription(App::subscription)
+        .theme(App::theme)
         .resizable(true)
         .centered()
         .run_with(|| App::new())
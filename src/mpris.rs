@@ -0,0 +1,320 @@
+use crate::api::zap;
+use crate::config::Config;
+use crate::epg::current_programme;
+use crate::m3u::Channel;
+use crate::mpv::{self, now_playing, play_channel, set_paused, NowPlaying};
+use crate::server::stream_id;
+
+use iced::futures::executor::block_on;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use zbus::blocking::connection::Builder;
+use zbus::blocking::Connection;
+use zbus::interface;
+use zbus::zvariant::{ObjectPath, OwnedValue, Value};
+
+const PATH: &str = "/org/mpris/MediaPlayer2";
+const BUS_NAME: &str = "org.mpris.MediaPlayer2.rustream";
+const PROGRAMME_REFRESH: Duration = Duration::from_secs(60);
+
+struct Root;
+
+#[interface(name = "org.mpris.MediaPlayer2")]
+impl Root {
+    fn raise(&self) {}
+
+    fn quit(&self) {}
+
+    #[zbus(property)]
+    fn can_quit(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn can_raise(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn has_track_list(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn identity(&self) -> String {
+        "Rustream".to_string()
+    }
+
+    #[zbus(property)]
+    fn desktop_entry(&self) -> String {
+        "rustream".to_string()
+    }
+
+    #[zbus(property)]
+    fn supported_uri_schemes(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    #[zbus(property)]
+    fn supported_mime_types(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+#[derive(Default)]
+struct Player {
+    now_playing: Option<NowPlaying>,
+    programme: Option<String>,
+    /// Played again by Play once stopped
+    last_channel: Option<Channel>,
+}
+
+#[interface(name = "org.mpris.MediaPlayer2.Player")]
+impl Player {
+    fn play(&self) {
+        match (&self.now_playing, &self.last_channel) {
+            (Some(_), _) => {
+                set_paused(false);
+            }
+            (None, Some(channel)) => play_channel(channel.clone()),
+            (None, None) => {}
+        }
+    }
+
+    fn pause(&self) {
+        set_paused(true);
+    }
+
+    fn play_pause(&self) {
+        match &self.now_playing {
+            Some(now_playing) => {
+                set_paused(!now_playing.paused);
+            }
+            None => self.play(),
+        }
+    }
+
+    fn stop(&self) {
+        mpv::stop();
+    }
+
+    fn next(&self) {
+        zap(1);
+    }
+
+    fn previous(&self) {
+        zap(-1);
+    }
+
+    /// Live streams can not be sought
+    fn seek(&self, _offset: i64) {}
+
+    fn set_position(&self, _track_id: ObjectPath<'_>, _position: i64) {}
+
+    fn open_uri(&self, _uri: String) {}
+
+    #[zbus(property)]
+    fn playback_status(&self) -> String {
+        match &self.now_playing {
+            Some(now_playing) if now_playing.paused => "Paused",
+            Some(_) => "Playing",
+            None => "Stopped",
+        }
+        .to_string()
+    }
+
+    #[zbus(property)]
+    fn metadata(&self) -> HashMap<String, OwnedValue> {
+        let mut metadata = HashMap::new();
+        let Some(now_playing) = &self.now_playing else {
+            return metadata;
+        };
+        let channel = &now_playing.channel;
+
+        let track_id = format!("/org/rustream/channel/c{}", stream_id(channel));
+        if let Ok(track_id) = ObjectPath::try_from(track_id) {
+            insert(&mut metadata, "mpris:trackid", Value::from(track_id));
+        }
+        insert(
+            &mut metadata,
+            "xesam:title",
+            Value::from(channel.name.clone()),
+        );
+        let artist = self.programme.clone().unwrap_or(channel.group.clone());
+        insert(&mut metadata, "xesam:artist", Value::from(vec![artist]));
+        insert(
+            &mut metadata,
+            "xesam:album",
+            Value::from(channel.group.clone()),
+        );
+        if let Some(logo_url) = &channel.logo_url {
+            insert(&mut metadata, "mpris:artUrl", Value::from(logo_url.clone()));
+        }
+        metadata
+    }
+
+    #[zbus(property)]
+    fn rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn minimum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn maximum_rate(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn volume(&self) -> f64 {
+        1.0
+    }
+
+    #[zbus(property)]
+    fn position(&self) -> i64 {
+        0
+    }
+
+    #[zbus(property)]
+    fn can_go_next(&self) -> bool {
+        self.now_playing.is_some()
+    }
+
+    #[zbus(property)]
+    fn can_go_previous(&self) -> bool {
+        self.now_playing.is_some()
+    }
+
+    #[zbus(property)]
+    fn can_play(&self) -> bool {
+        self.now_playing.is_some() || self.last_channel.is_some()
+    }
+
+    #[zbus(property)]
+    fn can_pause(&self) -> bool {
+        self.now_playing.is_some()
+    }
+
+    #[zbus(property)]
+    fn can_seek(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn can_control(&self) -> bool {
+        true
+    }
+}
+
+fn insert(metadata: &mut HashMap<String, OwnedValue>, key: &str, value: Value<'_>) {
+    if let Ok(value) = OwnedValue::try_from(value) {
+        metadata.insert(key.to_string(), value);
+    }
+}
+
+/// Registers the MPRIS service on the session bus in the background
+pub fn start() {
+    std::thread::spawn(|| {
+        if let Err(e) = run() {
+            eprintln!("Failed to register the MPRIS service: {}", e);
+        }
+    });
+}
+
+fn connect(builder: Builder<'_>) -> zbus::Result<Connection> {
+    builder
+        .name(BUS_NAME)?
+        .serve_at(PATH, Root)?
+        .serve_at(PATH, Player::default())?
+        .build()
+}
+
+/// Follows the playback and tells the bus whenever it changes
+fn run() -> zbus::Result<()> {
+    let connection = connect(Builder::session()?)?;
+    let player = connection.object_server().interface::<_, Player>(PATH)?;
+
+    let mut last_state = None;
+    let mut programme_checked_at = Instant::now();
+    loop {
+        let now_playing = now_playing();
+        let state = now_playing
+            .as_ref()
+            .map(|n| (n.channel.key(), n.url.clone(), n.paused));
+        let stale_programme = programme_checked_at.elapsed() > PROGRAMME_REFRESH;
+
+        if state != last_state || (now_playing.is_some() && stale_programme) {
+            let programme = now_playing
+                .as_ref()
+                .and_then(|n| current_programme(&Config::load(), &n.channel));
+            programme_checked_at = Instant::now();
+
+            let mut iface = player.get_mut();
+            if let Some(now_playing) = &now_playing {
+                iface.last_channel = Some(now_playing.channel.clone());
+            }
+            iface.now_playing = now_playing;
+            iface.programme = programme;
+            let emitter = player.signal_emitter();
+            let signals = [
+                block_on(iface.playback_status_changed(emitter)),
+                block_on(iface.metadata_changed(emitter)),
+                block_on(iface.can_play_changed(emitter)),
+                block_on(iface.can_pause_changed(emitter)),
+                block_on(iface.can_go_next_changed(emitter)),
+                block_on(iface.can_go_previous_changed(emitter)),
+            ];
+            // A missed signal is caught up by the next change
+            if let Some(Err(e)) = signals.into_iter().find(|signal| signal.is_err()) {
+                eprintln!("Failed to signal the MPRIS changes: {}", e);
+            }
+            last_state = state;
+        }
+        std::thread::sleep(Duration::from_secs(1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command, Stdio};
+    use zbus::blocking::Proxy;
+
+    #[test]
+    fn player_is_served_on_the_bus() {
+        // A private bus, so that the test does not need a desktop session
+        let Ok(mut daemon) = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            .spawn()
+        else {
+            eprintln!("Skipped as dbus-daemon is not installed");
+            return;
+        };
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take().unwrap())
+            .read_line(&mut address)
+            .unwrap();
+
+        let served = connect(Builder::address(address.trim()).unwrap()).unwrap();
+        let client = Builder::address(address.trim()).unwrap().build().unwrap();
+        let root = Proxy::new(&client, BUS_NAME, PATH, "org.mpris.MediaPlayer2").unwrap();
+        let player = Proxy::new(&client, BUS_NAME, PATH, "org.mpris.MediaPlayer2.Player").unwrap();
+
+        assert_eq!(root.get_property::<String>("Identity").unwrap(), "Rustream");
+        assert_eq!(
+            player.get_property::<String>("PlaybackStatus").unwrap(),
+            "Stopped"
+        );
+        assert!(!player.get_property::<bool>("CanPlay").unwrap());
+        assert!(!player.get_property::<bool>("CanGoNext").unwrap());
+
+        drop(served);
+        let _ = daemon.kill();
+        let _ = daemon.wait();
+    }
+}
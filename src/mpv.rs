@@ -1,53 +1,466 @@
+use crate::config::Config;
+use crate::embedded;
+use crate::events;
+use crate::hls::VariantChoice;
 use crate::m3u::Channel;
-use std::io::BufRead;
-use std::process::Stdio;
+use crate::server;
+use crate::state::UserState;
+use crate::views::ViewMessage;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::PathBuf;
+use std::process::{Child, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
 use std::{env::consts::OS, path::Path};
 use which::which;
 
-pub fn play(channel: Channel) -> Option<()> {
-    let path = get_mpv_path();
-    let args = get_play_args(&channel, path.clone()).unwrap();
-    let mut cmd = match std::process::Command::new(path)
+/// Player settings of each profile
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayerPreferences {
+    /// Volume mpv starts with, in percent
+    pub volume: Option<u32>,
+    /// Audio and subtitle languages to select, as mpv `--alang` and `--slang`
+    /// lists like `fr,en`
+    pub audio_languages: String,
+    pub subtitle_languages: String,
+}
+
+/// How playback moves on when a stream fails
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// Try the variants of the channel once its stream failed
+    pub failover: bool,
+    /// Attempts on each stream before moving to the next one
+    pub attempts: u32,
+    /// Seconds without any decoded frame before a stream is given up
+    pub timeout_secs: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            failover: true,
+            attempts: 1,
+            timeout_secs: 15,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum PlaybackEvent {
+    Playing {
+        channel: String,
+        url: String,
+        stream: usize,
+        streams: usize,
+    },
+    Failed {
+        channel: String,
+        url: String,
+        error: String,
+    },
+    Unavailable {
+        channel: String,
+    },
+    Stopped,
+}
+
+/// Stream being played, as seen by the remote control
+#[derive(Debug, Clone)]
+pub struct NowPlaying {
+    pub channel: Channel,
+    pub url: String,
+    pub stream: usize,
+    pub streams: usize,
+    pub paused: bool,
+}
+
+enum StreamEnd {
+    Finished,
+    Failed(String),
+    Cancelled,
+}
+
+/// Lines of the mpv output kept to explain a failure
+const OUTPUT_LINES: usize = 20;
+
+/// Bumped on every playback so the previous one stops trying its streams
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+static CURRENT: Mutex<Option<Child>> = Mutex::new(None);
+static MPV_PATH: LazyLock<String> = LazyLock::new(get_mpv_path);
+static NOW_PLAYING: Mutex<Option<NowPlaying>> = Mutex::new(None);
+
+fn notify(event: PlaybackEvent) {
+    events::send(ViewMessage::Playback(event));
+}
+
+/// Updates what is being played, unless a newer playback took over
+fn set_now_playing(generation: u64, now_playing: Option<NowPlaying>) {
+    let mut current = NOW_PLAYING.lock().unwrap();
+    if GENERATION.load(Ordering::SeqCst) == generation {
+        *current = now_playing;
+    }
+}
+
+/// Records the pause reported by mpv, unless a newer playback took over
+fn set_paused_state(generation: u64, paused: bool) {
+    let mut current = NOW_PLAYING.lock().unwrap();
+    if GENERATION.load(Ordering::SeqCst) == generation {
+        if let Some(current) = current.as_mut() {
+            current.paused = paused;
+        }
+    }
+}
+
+pub fn now_playing() -> Option<NowPlaying> {
+    NOW_PLAYING.lock().unwrap().clone()
+}
+
+/// Pauses or resumes mpv, returns false when nothing is playing
+pub fn set_paused(paused: bool) -> bool {
+    let mut now_playing = NOW_PLAYING.lock().unwrap();
+    let Some(current) = now_playing.as_mut() else {
+        return false;
+    };
+    let socket = ipc_socket_path(GENERATION.load(Ordering::SeqCst));
+    let command = serde_json::json!({ "command": ["set_property", "pause", paused] });
+    match write_ipc(&socket, &format!("{}\n", command)) {
+        Ok(()) => {
+            current.paused = paused;
+            true
+        }
+        Err(e) => {
+            eprintln!("Failed to send a command to mpv: {}", e);
+            false
+        }
+    }
+}
+
+/// Stops the current playback and the streams left to try
+pub fn stop() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    kill_current();
+    embedded::stop();
+    *NOW_PLAYING.lock().unwrap() = None;
+    notify(PlaybackEvent::Stopped);
+}
+
+/// Plays the channel with the saved retry policy, variant choice and player
+/// settings, and adds it to the watch history
+pub fn play_channel(channel: Channel) {
+    let state = UserState::update(|state| state.record_watched(&channel)).unwrap_or_else(|e| {
+        eprintln!("Failed to save the watch history: {}", e);
+        UserState::load()
+    });
+    let choice = state.variant_choice(&channel);
+    play(channel, Config::load().retry, choice, state.player);
+}
+
+/// Plays the channel in the background, replacing the current playback and
+/// falling back on its variants while the streams fail
+pub fn play(
+    channel: Channel,
+    policy: RetryPolicy,
+    choice: Option<VariantChoice>,
+    preferences: PlayerPreferences,
+) {
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    kill_current();
+
+    std::thread::spawn(move || {
+        let count = if policy.failover { usize::MAX } else { 1 };
+        let streams: Vec<&str> = channel.streams().take(count).collect();
+
+        for (index, url) in streams.iter().enumerate() {
+            for _ in 0..policy.attempts.max(1) {
+                set_now_playing(
+                    generation,
+                    Some(NowPlaying {
+                        channel: channel.clone(),
+                        url: url.to_string(),
+                        stream: index + 1,
+                        streams: streams.len(),
+                        paused: false,
+                    }),
+                );
+                notify(PlaybackEvent::Playing {
+                    channel: channel.name.clone(),
+                    url: url.to_string(),
+                    stream: index + 1,
+                    streams: streams.len(),
+                });
+
+                match play_stream(&channel, url, &policy, choice, &preferences, generation) {
+                    StreamEnd::Finished => {
+                        set_now_playing(generation, None);
+                        notify(PlaybackEvent::Stopped);
+                        return;
+                    }
+                    StreamEnd::Cancelled => return,
+                    StreamEnd::Failed(error) => {
+                        eprintln!("Failed to play {}: {}", url, error);
+                        notify(PlaybackEvent::Failed {
+                            channel: channel.name.clone(),
+                            url: url.to_string(),
+                            error,
+                        });
+                    }
+                }
+            }
+        }
+        set_now_playing(generation, None);
+        notify(PlaybackEvent::Unavailable {
+            channel: channel.name.clone(),
+        });
+    });
+}
+
+fn kill_current() {
+    if let Some(mut child) = CURRENT.lock().unwrap().take() {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+fn play_stream(
+    channel: &Channel,
+    url: &str,
+    policy: &RetryPolicy,
+    choice: Option<VariantChoice>,
+    preferences: &PlayerPreferences,
+    generation: u64,
+) -> StreamEnd {
+    let path = MPV_PATH.as_str();
+    let socket = ipc_socket_path(generation);
+    let _ = std::fs::remove_file(&socket);
+
+    // The embedded mode reads a single variant through the playback server,
+    // mpv playing its sound while ffmpeg decodes its picture
+    let source = Config::load()
+        .embedded_player
+        .then(|| server::playback_url(channel, url))
+        .flatten();
+    let variant = match &source {
+        Some(source) => choice
+            .unwrap_or(VariantChoice::Capped {
+                bandwidth: u64::MAX,
+            })
+            .variant_url(source),
+        None => choice.and_then(|choice| choice.pinned_url(url)),
+    };
+    let url = variant.as_deref().or(source.as_deref()).unwrap_or(url);
+    let mut args = get_play_args(channel, url, path.to_string(), preferences).unwrap();
+    if source.is_some() {
+        args.push("--no-video".to_string());
+    }
+    if let Some(choice) = choice.filter(|_| variant.is_none()) {
+        args.extend(choice.mpv_args());
+    }
+    args.push(format!("--input-ipc-server={}", socket.display()));
+    let mut child = match std::process::Command::new(path)
         .args(args)
         .stdout(Stdio::piped())
         .spawn()
     {
         Ok(child) => child,
-        Err(e) => {
-            eprintln!("Failed to spawn mpv process: {}", e);
-            return None;
-        }
+        Err(e) => return StreamEnd::Failed(format!("Failed to spawn mpv process: {}", e)),
     };
+    let output = read_output(&mut child);
+
+    {
+        // A newer playback may have started while mpv was spawning
+        let mut current = CURRENT.lock().unwrap();
+        if GENERATION.load(Ordering::SeqCst) != generation {
+            let _ = child.kill();
+            let _ = child.wait();
+            return StreamEnd::Cancelled;
+        }
+        *current = Some(child);
+    }
+    if source.is_some() {
+        embedded::start(url);
+    }
+
+    let end = wait_stream(&socket, policy, generation, output);
+    let _ = std::fs::remove_file(&socket);
+    if source.is_some() && !matches!(end, StreamEnd::Cancelled) {
+        embedded::stop();
+    }
+    end
+}
 
-    let status = cmd.wait().unwrap();
-
-    if !status.success() {
-        if let Some(stdout) = cmd.stdout.take() {
-            let mut error = String::new();
-            let mut lines = std::io::BufReader::new(stdout).lines();
-            let mut first = true;
-            while let Some(line) = lines.next() {
-                error += &line.unwrap();
-                if !first {
-                    error += "\n";
-                } else {
-                    first = false;
+fn wait_stream(
+    socket: &Path,
+    policy: &RetryPolicy,
+    generation: u64,
+    output: Receiver<String>,
+) -> StreamEnd {
+    let events = connect_ipc(socket);
+    let timeout = Duration::from_secs(policy.timeout_secs);
+    let started_at = Instant::now();
+    // Without IPC the only failure we can see is the exit status
+    let mut started = events.is_none();
+    let mut file_error = None;
+
+    loop {
+        if GENERATION.load(Ordering::SeqCst) != generation {
+            return StreamEnd::Cancelled;
+        }
+
+        for event in events.iter().flat_map(|events| events.try_iter()) {
+            match event.get("event").and_then(|e| e.as_str()) {
+                Some("playback-restart") => started = true,
+                Some("end-file") if event["reason"] == "error" => {
+                    file_error = event["file_error"].as_str().map(|e| e.to_string());
                 }
+                // Pauses from the mpv window as well as ours
+                Some("property-change") if event["name"] == "pause" => {
+                    if let Some(paused) = event["data"].as_bool() {
+                        set_paused_state(generation, paused);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut current = CURRENT.lock().unwrap();
+        let Some(child) = current.as_mut() else {
+            return StreamEnd::Cancelled;
+        };
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                // mpv may leave children holding the pipe open
+                let output = output
+                    .recv_timeout(Duration::from_secs(1))
+                    .unwrap_or_default();
+                current.take();
+                return match (status.success(), file_error) {
+                    (true, None) => StreamEnd::Finished,
+                    (_, Some(error)) => StreamEnd::Failed(error),
+                    (false, None) if !output.is_empty() => StreamEnd::Failed(output),
+                    (false, None) => {
+                        StreamEnd::Failed("Mpv encountered an unknown error".to_string())
+                    }
+                };
+            }
+            Ok(None) if !started && started_at.elapsed() > timeout => {
+                let _ = child.kill();
+                let _ = child.wait();
+                current.take();
+                return StreamEnd::Failed("No data received before the timeout".to_string());
             }
-            if !error.is_empty() {
-                eprintln!("{}", error);
-            } else {
-                eprintln!("Mpv encountered an unknown error");
+            Ok(None) => {}
+            Err(e) => return StreamEnd::Failed(e.to_string()),
+        }
+        drop(current);
+        std::thread::sleep(Duration::from_millis(100));
+    }
+}
+
+/// Reads the output of mpv while it plays, so that it never blocks on a
+/// full pipe; its last lines are sent once it is closed, to explain a failure
+fn read_output(child: &mut Child) -> Receiver<String> {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    if let Some(stdout) = child.stdout.take() {
+        std::thread::spawn(move || {
+            let mut lines = VecDeque::new();
+            for line in BufReader::new(stdout).lines().map_while(|line| line.ok()) {
+                if lines.len() == OUTPUT_LINES {
+                    lines.pop_front();
+                }
+                lines.push_back(line);
             }
+            let _ = sender.send(Vec::from(lines).join("\n"));
+        });
+    }
+    receiver
+}
+
+fn ipc_socket_path(generation: u64) -> PathBuf {
+    let name = format!("rustream-mpv-{}-{}", std::process::id(), generation);
+    if OS == "windows" {
+        return PathBuf::from(format!(r"\\.\pipe\{}", name));
+    }
+    std::env::temp_dir().join(name + ".sock")
+}
+
+/// Reads the JSON events mpv writes on its IPC socket, which is created
+/// shortly after the process started, with the changes of its pause
+fn connect_ipc(socket: &Path) -> Option<std::sync::mpsc::Receiver<serde_json::Value>> {
+    let mut stream = (0..20).find_map(|_| {
+        let stream = open_ipc(socket);
+        if stream.is_none() {
+            std::thread::sleep(Duration::from_millis(100));
         }
+        stream
+    })?;
+    let command = serde_json::json!({ "command": ["observe_property", 1, "pause"] });
+    if let Err(e) = stream.write_all(format!("{}\n", command).as_bytes()) {
+        eprintln!("Failed to send a command to mpv: {}", e);
     }
-    return Some(());
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else { break };
+            if let Ok(event) = serde_json::from_str(&line) {
+                if sender.send(event).is_err() {
+                    break;
+                }
+            }
+        }
+    });
+    Some(receiver)
 }
 
-fn get_play_args(channel: &Channel, path: String) -> Result<Vec<String>, std::io::Error> {
-    let mut args = vec![channel.url.clone()];
+/// Connection to the IPC socket of mpv
+trait Ipc: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Ipc for T {}
 
-    if channel.url.ends_with(".mkv") || channel.url.ends_with(".mp4") {
+#[cfg(unix)]
+fn open_ipc(socket: &Path) -> Option<Box<dyn Ipc>> {
+    let stream = std::os::unix::net::UnixStream::connect(socket).ok()?;
+    Some(Box::new(stream))
+}
+
+#[cfg(not(unix))]
+fn open_ipc(socket: &Path) -> Option<Box<dyn Ipc>> {
+    let pipe = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(socket)
+        .ok()?;
+    Some(Box::new(pipe))
+}
+
+#[cfg(unix)]
+fn write_ipc(socket: &Path, line: &str) -> std::io::Result<()> {
+    let mut stream = std::os::unix::net::UnixStream::connect(socket)?;
+    stream.write_all(line.as_bytes())
+}
+
+#[cfg(not(unix))]
+fn write_ipc(socket: &Path, line: &str) -> std::io::Result<()> {
+    let mut pipe = std::fs::OpenOptions::new().write(true).open(socket)?;
+    pipe.write_all(line.as_bytes())
+}
+
+fn get_play_args(
+    channel: &Channel,
+    url: &str,
+    path: String,
+    preferences: &PlayerPreferences,
+) -> Result<Vec<String>, std::io::Error> {
+    let mut args = vec![url.to_string()];
+
+    if url.ends_with(".mkv") || url.ends_with(".mp4") {
         args.push("--save-position-on-quit".to_string());
     }
     if OS == "macos" && path != "mpv" {
@@ -56,6 +469,15 @@ fn get_play_args(channel: &Channel, path: String) -> Result<Vec<String>, std::io
                 + find_macos_bin("yt-dlp".to_string()).as_str(),
         );
     }
+    if let Some(volume) = preferences.volume {
+        args.push(format!("--volume={}", volume));
+    }
+    if !preferences.audio_languages.is_empty() {
+        args.push(format!("--alang={}", preferences.audio_languages));
+    }
+    if !preferences.subtitle_languages.is_empty() {
+        args.push(format!("--slang={}", preferences.subtitle_languages));
+    }
     args.push(format!("--title={}", channel.name));
     args.push("--msg-level=all=error".to_string());
 
@@ -97,7 +519,7 @@ const MACOS_POTENTIAL_PATHS: [&str; 3] = [
     "/usr/local/bin",    // Homebrew on AMD64 Mac
 ];
 
-fn find_macos_bin(bin: String) -> String {
+pub fn find_macos_bin(bin: String) -> String {
     return MACOS_POTENTIAL_PATHS
         .iter()
         .map(|path| {
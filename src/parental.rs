@@ -0,0 +1,129 @@
+use crate::config::Config;
+use crate::m3u::Channel;
+use crate::server::same_secret;
+use crate::store;
+use crate::types::Result;
+
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// PBKDF2 rounds of the PINs set from now on
+const PIN_ITERATIONS: u32 = 600_000;
+
+/// Set once the PIN is entered, until the application is closed or locked
+/// again
+static UNLOCKED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParentalConfig {
+    /// PBKDF2-HMAC-SHA256 of the PIN, nothing is locked until one is set
+    pub pin_hash: Option<String>,
+    pub salt: String,
+    /// PBKDF2 rounds of the hash
+    pub iterations: u32,
+    /// Groups whose title contains one of these words are locked, whatever
+    /// the case
+    pub keywords: Vec<String>,
+}
+
+impl Default for ParentalConfig {
+    fn default() -> Self {
+        Self {
+            pin_hash: None,
+            salt: String::new(),
+            iterations: PIN_ITERATIONS,
+            keywords: ["XXX", "Adult", "Adulte", "18+"]
+                .into_iter()
+                .map(String::from)
+                .collect(),
+        }
+    }
+}
+
+impl ParentalConfig {
+    fn hash(&self, pin: &str) -> String {
+        let mut hash = [0u8; 32];
+        pbkdf2::pbkdf2_hmac::<Sha256>(
+            pin.as_bytes(),
+            self.salt.as_bytes(),
+            self.iterations,
+            &mut hash,
+        );
+        hash.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    /// Replaces the PIN, an empty one turning the controls off
+    pub fn set_pin(&mut self, pin: &str) -> Result<()> {
+        if pin.is_empty() {
+            self.pin_hash = None;
+            return Ok(());
+        }
+        let mut salt = [0u8; 16];
+        getrandom::getrandom(&mut salt)?;
+        self.salt = salt.iter().map(|byte| format!("{:02x}", byte)).collect();
+        self.iterations = PIN_ITERATIONS;
+        self.pin_hash = Some(self.hash(pin));
+        Ok(())
+    }
+
+    pub fn check_pin(&self, pin: &str) -> bool {
+        self.pin_hash
+            .as_deref()
+            .is_some_and(|hash| same_secret(hash, &self.hash(pin)))
+    }
+
+    /// Whether locked groups and channels are hidden for now
+    pub fn is_restricted(&self) -> bool {
+        self.pin_hash.is_some() && !UNLOCKED.load(Ordering::Relaxed)
+    }
+
+    /// Whether locked groups and channels are hidden from the server, which
+    /// the PIN entered in the interface does not unlock
+    pub fn is_restricted_for_export(&self) -> bool {
+        self.pin_hash.is_some()
+    }
+
+    pub fn matches_keywords(&self, channel: &Channel) -> bool {
+        let group_title = channel
+            .attributes
+            .get("group-title")
+            .unwrap_or(&channel.group);
+        [group_title, &channel.group].iter().any(|group| {
+            let group = group.to_lowercase();
+            self.keywords
+                .iter()
+                .any(|keyword| !keyword.is_empty() && group.contains(&keyword.to_lowercase()))
+        })
+    }
+}
+
+/// Shows the locked groups and channels if the PIN is the right one
+pub fn unlock(pin: &str) -> bool {
+    let unlocked = Config::load().parental.check_pin(pin);
+    if unlocked {
+        UNLOCKED.store(true, Ordering::Relaxed);
+        store::touch();
+    }
+    unlocked
+}
+
+pub fn lock() {
+    UNLOCKED.store(false, Ordering::Relaxed);
+    store::touch();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pin_is_checked_against_its_hash() {
+        let mut parental = ParentalConfig::default();
+        parental.set_pin("1234").unwrap();
+        assert_eq!(parental.iterations, PIN_ITERATIONS);
+        assert!(parental.check_pin("1234"));
+        assert!(!parental.check_pin("4321"));
+    }
+}
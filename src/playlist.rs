@@ -0,0 +1,376 @@
+use crate::cleanup::Cleaner;
+use crate::config::{cache_dir, Config};
+use crate::duplicates::merge_duplicates;
+use crate::events;
+use crate::m3u::{group_channels, parse_m3u, Channel, Group};
+use crate::server::hash;
+use crate::t;
+use crate::types::Result;
+use crate::views::ViewMessage;
+
+use iced::futures::channel::oneshot;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use url::Url;
+
+/// Cached copies being downloaded in the background
+static DOWNLOADING: Mutex<BTreeSet<PathBuf>> = Mutex::new(BTreeSet::new());
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SourceKind {
+    File {
+        path: String,
+    },
+    Url {
+        url: String,
+    },
+    Xtream {
+        server: String,
+        username: String,
+        password: String,
+    },
+}
+
+/// A playlist the user registered in the settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlaylistSource {
+    /// Prefix of the keys of its channels in the user state, the name the
+    /// source was added with
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub kind: SourceKind,
+    pub enabled: bool,
+    /// Hours between two downloads of a remote playlist
+    pub refresh_hours: Option<u32>,
+    #[serde(default)]
+    pub refresh_on_startup: bool,
+    /// XMLTV guide of the playlist, Xtream servers provide their own
+    #[serde(default)]
+    pub epg_url: Option<String>,
+}
+
+/// Channels added and removed by the last download of the sources
+#[derive(Debug, Clone, Default)]
+pub struct PlaylistDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    /// Sources that failed to load, their previous copy being kept if any
+    pub errors: Vec<String>,
+}
+
+impl PlaylistDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.errors.is_empty()
+    }
+
+    pub fn summary(&self) -> String {
+        let mut summary = t!(
+            "playlist-diff",
+            added = self.added.len(),
+            removed = self.removed.len()
+        );
+        for error in &self.errors {
+            summary += &format!("\n{}", error);
+        }
+        for (sign, names) in [("+", &self.added), ("-", &self.removed)] {
+            for name in names.iter().take(5) {
+                summary += &format!("\n{} {}", sign, name);
+            }
+            if names.len() > 5 {
+                summary += &format!(
+                    "\n{} {}",
+                    sign,
+                    t!("playlist-diff-more", count = names.len() - 5)
+                );
+            }
+        }
+        summary
+    }
+
+    fn extend(&mut self, other: PlaylistDiff) {
+        self.added.extend(other.added);
+        self.removed.extend(other.removed);
+        self.errors.extend(other.errors);
+    }
+}
+
+impl PlaylistSource {
+    pub fn new(name: String, kind: SourceKind) -> Self {
+        Self {
+            id: name.clone(),
+            name,
+            kind,
+            enabled: true,
+            refresh_hours: None,
+            refresh_on_startup: false,
+            epg_url: None,
+        }
+    }
+
+    pub fn is_remote(&self) -> bool {
+        !matches!(self.kind, SourceKind::File { .. })
+    }
+
+    /// A remote source is due once its cached copy is older than its refresh
+    /// interval, the modification time of the cache is the last download
+    pub fn is_due(&self) -> bool {
+        let hours = match self.refresh_hours {
+            Some(hours) if self.enabled && self.is_remote() => hours,
+            _ => return false,
+        };
+        let modified = std::fs::metadata(self.cache_path()).and_then(|m| m.modified());
+        match modified {
+            Ok(modified) => {
+                let age = SystemTime::now()
+                    .duration_since(modified)
+                    .unwrap_or_default();
+                age >= Duration::from_secs(hours as u64 * 3600)
+            }
+            Err(_) => true,
+        }
+    }
+
+    pub fn location(&self) -> String {
+        match &self.kind {
+            SourceKind::File { path } => path.clone(),
+            SourceKind::Url { url } => url.clone(),
+            SourceKind::Xtream {
+                server, username, ..
+            } => format!("{} ({})", server, username),
+        }
+    }
+
+    /// Remote sources are downloaded in the cache directory and parsed from
+    /// there, the cached copy is reused unless `download` is set
+    pub fn load(&self, download: bool) -> Result<(Vec<Group>, PlaylistDiff)> {
+        let previous = if self.is_remote() && download {
+            parse_m3u(&self.cache_path().to_string_lossy()).ok()
+        } else {
+            None
+        };
+
+        let path = match &self.kind {
+            SourceKind::File { path } => PathBuf::from(path),
+            SourceKind::Url { url } => self.fetch(url, self.cache_path(), download)?,
+            SourceKind::Xtream {
+                server,
+                username,
+                password,
+            } => self.fetch(
+                &xtream_url(server, username, password, "get.php")?,
+                self.cache_path(),
+                download,
+            )?,
+        };
+        if download {
+            if let Err(e) = self.epg_path(true) {
+                eprintln!("Failed to download the guide of {}: {}", self.name, e);
+            }
+        }
+
+        let mut groups = parse_m3u(&path.to_string_lossy())?;
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        for channel in groups.iter_mut().flat_map(|g| g.channels.iter_mut()) {
+            channel.source = self.name.clone();
+            channel.source_id = self.id.clone();
+            let count = seen.entry(channel.original_name.clone()).or_default();
+            channel.duplicate = *count;
+            *count += 1;
+        }
+
+        let diff = match previous {
+            Some(previous) => diff_groups(&previous, &groups),
+            None => PlaylistDiff::default(),
+        };
+        Ok((groups, diff))
+    }
+
+    fn cache_path(&self) -> PathBuf {
+        self.cache_file("m3u")
+    }
+
+    /// Named after the location of the source, so that renaming it keeps its
+    /// cache and two sources never share one
+    fn cache_file(&self, extension: &str) -> PathBuf {
+        cache_dir()
+            .join("sources")
+            .join(format!("{}.{}", hash(&self.location()), extension))
+    }
+
+    pub fn epg_location(&self) -> Option<String> {
+        if let Some(url) = &self.epg_url {
+            return Some(url.clone());
+        }
+        match &self.kind {
+            SourceKind::Xtream {
+                server,
+                username,
+                password,
+            } => xtream_url(server, username, password, "xmltv.php").ok(),
+            _ => None,
+        }
+    }
+
+    /// Cached copy of the guide, downloaded when missing or when `download`
+    /// is set, none when the source has no guide
+    pub fn epg_path(&self, download: bool) -> Result<Option<PathBuf>> {
+        let Some(location) = self.epg_location() else {
+            return Ok(None);
+        };
+        if !location.starts_with("http://") && !location.starts_with("https://") {
+            return Ok(Some(PathBuf::from(location)));
+        }
+        let path = self.fetch(&location, self.cache_file("xml"), download)?;
+        Ok(Some(path))
+    }
+
+    /// Cached copy of the guide, its download being started in the background
+    /// when it is missing
+    pub fn cached_epg_path(&self) -> Option<PathBuf> {
+        let location = self.epg_location()?;
+        if !location.starts_with("http://") && !location.starts_with("https://") {
+            return Some(PathBuf::from(location));
+        }
+        let path = self.cache_file("xml");
+        if path.exists() {
+            return Some(path);
+        }
+
+        if DOWNLOADING.lock().unwrap().insert(path.clone()) {
+            let source = self.clone();
+            std::thread::spawn(move || {
+                if let Err(e) = source.epg_path(true) {
+                    eprintln!("Failed to download the guide of {}: {}", source.name, e);
+                }
+                DOWNLOADING.lock().unwrap().remove(&path);
+            });
+        }
+        None
+    }
+
+    fn fetch(&self, url: &str, path: PathBuf, download: bool) -> Result<PathBuf> {
+        if !download && path.exists() {
+            return Ok(path);
+        }
+
+        let content = reqwest::blocking::get(url)?.error_for_status()?.bytes()?;
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        // Replaced at once, as the previous copy may be read meanwhile
+        let partial = path.with_extension("part");
+        std::fs::write(&partial, content)?;
+        std::fs::rename(&partial, &path)?;
+        println!("Downloaded {} to {}", self.name, path.display());
+        Ok(path)
+    }
+}
+
+fn xtream_url(server: &str, username: &str, password: &str, endpoint: &str) -> Result<String> {
+    let mut url = Url::parse(server)?.join(endpoint)?;
+    url.query_pairs_mut()
+        .append_pair("username", username)
+        .append_pair("password", password);
+    if endpoint == "get.php" {
+        url.query_pairs_mut()
+            .append_pair("type", "m3u_plus")
+            .append_pair("output", "ts");
+    }
+    Ok(url.to_string())
+}
+
+fn diff_groups(previous: &[Group], current: &[Group]) -> PlaylistDiff {
+    let names = |groups: &[Group]| -> HashSet<String> {
+        groups
+            .iter()
+            .flat_map(|g| g.channels.iter().map(|c| c.name.clone()))
+            .collect()
+    };
+    let previous = names(previous);
+    let current = names(current);
+
+    let mut diff = PlaylistDiff {
+        added: current.difference(&previous).cloned().collect(),
+        removed: previous.difference(&current).cloned().collect(),
+        errors: Vec::new(),
+    };
+    diff.added.sort();
+    diff.removed.sort();
+    diff
+}
+
+/// Loads every enabled source, cleans the channel names, folds duplicates
+/// and merges groups sharing the same name, optionally prefixing them with
+/// their source
+pub fn load_sources(
+    config: &Config,
+    download: impl Fn(&PlaylistSource) -> bool,
+) -> (Vec<Group>, PlaylistDiff) {
+    let mut channels: Vec<Channel> = Vec::new();
+    let mut diff = PlaylistDiff::default();
+
+    for source in config.sources.iter().filter(|s| s.enabled) {
+        let download = download(source);
+        let loaded = source.load(download).or_else(|e| {
+            eprintln!("Failed to load {}: {}", source.name, e);
+            diff.errors.push(t!(
+                "playlist-load-failed",
+                source = source.name.clone(),
+                error = e.to_string()
+            ));
+            // Falls back to the copy downloaded last time
+            if download {
+                source.load(false)
+            } else {
+                Err(e)
+            }
+        });
+        match loaded {
+            Ok((groups, source_diff)) => {
+                println!("Loaded {} groups from {}", groups.len(), source.name);
+                channels.extend(groups.into_iter().flat_map(|g| g.channels));
+                diff.extend(source_diff);
+            }
+            Err(e) => eprintln!("Failed to load the cached copy of {}: {}", source.name, e),
+        }
+    }
+
+    Cleaner::new(&config.cleanup).apply(&mut channels);
+    if config.merge_duplicates {
+        channels = merge_duplicates(channels, config.preferred_quality.as_deref());
+    }
+
+    if config.prefix_groups {
+        for channel in channels.iter_mut() {
+            channel.group = format!("{} | {}", channel.source, channel.group);
+        }
+    }
+
+    (group_channels(channels), diff)
+}
+
+/// Downloads the sources due for a refresh on a separate thread, so the
+/// interface keeps running while the playlists are fetched; `None` if the
+/// thread died, the loaded playlist being kept then
+pub async fn refresh_sources(
+    config: Config,
+    download: fn(&PlaylistSource) -> bool,
+) -> Option<(Vec<Group>, PlaylistDiff)> {
+    let (sender, receiver) = oneshot::channel();
+    std::thread::spawn(move || {
+        let _ = sender.send(load_sources(&config, download));
+    });
+    receiver.await.ok()
+}
+
+/// Loads the sources on a separate thread, the playlist being published
+/// through the interface events once loaded
+pub fn reload_in_background(config: Config, download: bool) {
+    std::thread::spawn(move || {
+        let (groups, diff) = load_sources(&config, |_| download);
+        events::send(ViewMessage::PlaylistRefreshed(Arc::new(groups), diff));
+    });
+}
@@ -0,0 +1,59 @@
+use crate::config::{config_dir, Config};
+use crate::parental;
+use crate::server::hash;
+use crate::store;
+use crate::types::Result;
+
+use std::sync::RwLock;
+
+/// Profile of the person using the application, `None` for the main one
+/// whose files are kept at the root of the config directory
+static CURRENT: RwLock<Option<String>> = RwLock::new(None);
+
+pub fn current() -> Option<String> {
+    CURRENT.read().unwrap().clone()
+}
+
+/// Switches to a profile, locking the parental controls again
+pub fn select(name: Option<String>) {
+    parental::lock();
+    *CURRENT.write().unwrap() = name;
+    store::touch();
+}
+
+fn directory(name: &str) -> String {
+    format!("profiles/{}", hash(name))
+}
+
+/// Path of a file of the current profile, relative to the config directory
+pub fn file(filename: &str) -> String {
+    match current() {
+        Some(name) => format!("{}/{}", directory(&name), filename),
+        None => filename.to_string(),
+    }
+}
+
+/// Adds a profile, its files being created on its first use
+pub fn add(name: &str) -> Result<()> {
+    let mut config = Config::load();
+    if !name.is_empty() && !config.profiles.iter().any(|p| p == name) {
+        config.profiles.push(name.to_string());
+        config.save()?;
+    }
+    Ok(())
+}
+
+/// Removes a profile with its favorites, history and edits
+pub fn remove(name: &str) -> Result<()> {
+    let mut config = Config::load();
+    config.profiles.retain(|p| p != name);
+    config.save()?;
+    if current().as_deref() == Some(name) {
+        select(None);
+    }
+    let path = config_dir().join(directory(name));
+    if path.exists() {
+        std::fs::remove_dir_all(path)?;
+    }
+    Ok(())
+}
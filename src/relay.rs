@@ -0,0 +1,310 @@
+use std::collections::{HashMap, VecDeque};
+use std::io::Read;
+use std::sync::{Arc, Condvar, LazyLock, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Bytes read from upstream at once
+const CHUNK_SIZE: usize = 1 << 16;
+/// Bytes kept for the clients joining late, the oldest being dropped past it
+/// on live streams
+const BUFFER_SIZE: usize = 32 << 20;
+/// Time a finished resource of known length stays shared, enough for the
+/// players to fetch the same HLS segment
+const KEPT: Duration = Duration::from_secs(30);
+
+/// Upstream resources being relayed by URL and request headers, each read
+/// once whatever the number of clients reading it
+static RELAYS: LazyLock<Mutex<HashMap<String, Arc<Relay>>>> = LazyLock::new(Default::default);
+
+struct Relay {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+#[derive(Default)]
+struct State {
+    /// Set once the first client opened the resource
+    content_type: Option<String>,
+    /// Whether the resource has a length, unlike live streams which are not
+    /// worth sharing once ended
+    finite: bool,
+    chunks: VecDeque<Arc<[u8]>>,
+    /// Number of chunks dropped from the front of `chunks`
+    dropped: usize,
+    size: usize,
+    end: Option<End>,
+    readers: usize,
+}
+
+enum End {
+    Finished(Instant),
+    Failed(String),
+    /// Not opened by the first client, or left by every client
+    Abandoned,
+}
+
+/// Outcome of joining the readers of a resource
+pub enum Joined {
+    Reader(RelayReader),
+    /// Nobody reads the resource yet, it is up to the caller to open it
+    First(Pending),
+}
+
+/// Resource being opened by the first client, the others waiting for it
+pub struct Pending {
+    key: String,
+    relay: Arc<Relay>,
+    started: bool,
+}
+
+/// Reads the shared resource from its beginning, or from the oldest bytes
+/// kept of a live stream
+pub struct RelayReader {
+    relay: Arc<Relay>,
+    next: usize,
+    chunk: Arc<[u8]>,
+    offset: usize,
+}
+
+impl Relay {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+
+    fn reader(self: &Arc<Self>, state: &mut State) -> RelayReader {
+        state.readers += 1;
+        RelayReader {
+            relay: self.clone(),
+            next: 0,
+            chunk: Arc::new([]),
+            offset: 0,
+        }
+    }
+}
+
+/// Joins the clients of an upstream URL requested with the same headers,
+/// waiting for the first one to open it if it is being opened
+pub fn join(url: &str, headers: &[(&str, String)]) -> Joined {
+    let mut key = url.to_string();
+    for (name, value) in headers {
+        key += &format!("\n{}: {}", name, value);
+    }
+    loop {
+        let relay = {
+            let mut relays = RELAYS.lock().unwrap();
+            relays.retain(|_, relay| {
+                let state = relay.state();
+                match state.end {
+                    Some(End::Finished(at)) => state.finite && at.elapsed() < KEPT,
+                    Some(End::Failed(_)) | Some(End::Abandoned) => false,
+                    None => true,
+                }
+            });
+            match relays.get(&key) {
+                Some(relay) => relay.clone(),
+                None => {
+                    let relay = Arc::new(Relay {
+                        state: Mutex::default(),
+                        changed: Condvar::new(),
+                    });
+                    relays.insert(key.clone(), relay.clone());
+                    return Joined::First(Pending {
+                        key,
+                        relay,
+                        started: false,
+                    });
+                }
+            }
+        };
+
+        let mut state = relay.state();
+        while state.content_type.is_none() && state.end.is_none() {
+            state = relay.changed.wait(state).unwrap();
+        }
+        // A resource that was not opened is tried again by this client
+        if state.content_type.is_some() && !matches!(state.end, Some(End::Abandoned)) {
+            return Joined::Reader(relay.reader(&mut state));
+        }
+    }
+}
+
+impl Pending {
+    /// Shares the body of the resource opened by the first client, with its
+    /// length when it is known
+    pub fn start(
+        mut self,
+        content_type: String,
+        length: Option<u64>,
+        body: impl Read + Send + 'static,
+    ) -> RelayReader {
+        self.started = true;
+        let relay = self.relay.clone();
+        let reader = {
+            let mut state = relay.state();
+            state.content_type = Some(content_type);
+            state.finite = length.is_some();
+            relay.reader(&mut state)
+        };
+        relay.changed.notify_all();
+        std::thread::spawn(move || fill(relay, body));
+        reader
+    }
+}
+
+/// The resource is not shared once the first client gave up opening it,
+/// like HLS playlists which are rewritten for each client
+impl Drop for Pending {
+    fn drop(&mut self) {
+        if self.started {
+            return;
+        }
+        self.relay.state().end = Some(End::Abandoned);
+        self.relay.changed.notify_all();
+        let mut relays = RELAYS.lock().unwrap();
+        if relays
+            .get(&self.key)
+            .is_some_and(|relay| Arc::ptr_eq(relay, &self.relay))
+        {
+            relays.remove(&self.key);
+        }
+    }
+}
+
+/// Reads the upstream body into the relay until it ends or nobody reads it
+fn fill(relay: Arc<Relay>, mut body: impl Read) {
+    let mut buffer = vec![0; CHUNK_SIZE];
+    loop {
+        let read = body.read(&mut buffer);
+        let mut state = relay.state();
+        match read {
+            Ok(0) => {
+                // Clients joining later would get the beginning of the stream
+                if state.dropped > 0 {
+                    state.finite = false;
+                }
+                state.end = Some(End::Finished(Instant::now()));
+            }
+            Ok(read) => {
+                state.size += read;
+                state.chunks.push_back(buffer[..read].into());
+                while state.size > BUFFER_SIZE {
+                    let Some(chunk) = state.chunks.pop_front() else {
+                        break;
+                    };
+                    state.size -= chunk.len();
+                    state.dropped += 1;
+                }
+                if state.readers == 0 {
+                    state.end = Some(End::Abandoned);
+                }
+            }
+            Err(e) => state.end = Some(End::Failed(e.to_string())),
+        }
+        let ended = state.end.is_some();
+        drop(state);
+        relay.changed.notify_all();
+        if ended {
+            return;
+        }
+    }
+}
+
+impl RelayReader {
+    pub fn content_type(&self) -> String {
+        self.relay.state().content_type.clone().unwrap_or_default()
+    }
+}
+
+impl Read for RelayReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.offset == self.chunk.len() {
+            let mut state = self.relay.state();
+            loop {
+                self.next = self.next.max(state.dropped);
+                if let Some(chunk) = state.chunks.get(self.next - state.dropped) {
+                    self.chunk = chunk.clone();
+                    self.offset = 0;
+                    self.next += 1;
+                    break;
+                }
+                match &state.end {
+                    Some(End::Failed(error)) => return Err(std::io::Error::other(error.clone())),
+                    Some(_) => return Ok(0),
+                    None => state = self.relay.changed.wait(state).unwrap(),
+                }
+            }
+        }
+        let read = buf.len().min(self.chunk.len() - self.offset);
+        buf[..read].copy_from_slice(&self.chunk[self.offset..self.offset + read]);
+        self.offset += read;
+        Ok(read)
+    }
+}
+
+impl Drop for RelayReader {
+    fn drop(&mut self) {
+        self.relay.state().readers -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn read_all(mut reader: RelayReader) -> String {
+        let mut content = String::new();
+        reader.read_to_string(&mut content).unwrap();
+        content
+    }
+
+    #[test]
+    fn clients_share_the_first_opening() {
+        let url = "http://relay.test/shared";
+        let Joined::First(pending) = join(url, &[]) else {
+            panic!("the first client should open the resource");
+        };
+        let joining = std::thread::spawn(move || match join(url, &[]) {
+            Joined::Reader(reader) => read_all(reader),
+            Joined::First(_) => panic!("the resource is being opened"),
+        });
+        std::thread::sleep(Duration::from_millis(100));
+
+        let reader = pending.start("video/mp2t".to_string(), Some(7), Cursor::new("segment"));
+        assert_eq!(reader.content_type(), "video/mp2t");
+        assert_eq!(read_all(reader), "segment");
+        assert_eq!(joining.join().unwrap(), "segment");
+
+        // Finished resources stay shared for a while
+        let Joined::Reader(reader) = join(url, &[]) else {
+            panic!("the finished resource should be kept");
+        };
+        assert_eq!(read_all(reader), "segment");
+        // Unless they are asked for with other headers
+        let headers = [("User-Agent", "player".to_string())];
+        assert!(matches!(join(url, &headers), Joined::First(_)));
+    }
+
+    #[test]
+    fn ended_live_streams_are_not_shared() {
+        let url = "http://relay.test/live";
+        let Joined::First(pending) = join(url, &[]) else {
+            panic!("the first client should open the resource");
+        };
+        let reader = pending.start("video/mp2t".to_string(), None, Cursor::new("live"));
+        assert_eq!(read_all(reader), "live");
+        assert!(matches!(join(url, &[]), Joined::First(_)));
+    }
+
+    #[test]
+    fn resources_not_opened_are_tried_again() {
+        let url = "http://relay.test/playlist";
+        let Joined::First(pending) = join(url, &[]) else {
+            panic!("the first client should open the resource");
+        };
+        let joining = std::thread::spawn(move || matches!(join(url, &[]), Joined::First(_)));
+        std::thread::sleep(Duration::from_millis(100));
+        drop(pending);
+        assert!(joining.join().unwrap());
+    }
+}
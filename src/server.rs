@@ -0,0 +1,620 @@
+use crate::api::{self, ApiConfig};
+use crate::config::Config;
+use crate::epg::curated_epg;
+use crate::hdhomerun::{self, HdHomeRunConfig};
+use crate::m3u::{to_m3u, Channel};
+use crate::mpv::now_playing;
+use crate::relay::{self, Joined};
+use crate::state::{UserState, FAVORITES_GROUP};
+use crate::store;
+use crate::types::Result;
+
+use hmac::{Hmac, Mac};
+use regex::Regex;
+use reqwest::blocking::Client;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::io::Read;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+use tiny_http::{Header, Request, Response, ResponseBox, Server};
+use url::{form_urlencoded, Url};
+
+static SERVER: Mutex<Option<Arc<Server>>> = Mutex::new(None);
+static CLIENT: LazyLock<Client> = LazyLock::new(|| {
+    Client::builder()
+        .connect_timeout(Duration::from_secs(10))
+        // Live streams are read for as long as the client watches them
+        .timeout(None)
+        .build()
+        .unwrap_or_default()
+});
+static URI_ATTRIBUTE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"URI="([^"]+)""#).unwrap());
+/// Signs the upstream URLs handed out by the proxy, so that it only relays
+/// the resources of the channels
+static SIGNING_KEY: LazyLock<[u8; 32]> = LazyLock::new(|| {
+    let mut key = [0; 32];
+    getrandom::getrandom(&mut key).expect("Failed to generate the signing key");
+    key
+});
+/// Visible channels with the store revision they were built from
+static CHANNELS: Mutex<Option<(u64, Arc<Vec<Channel>>)>> = Mutex::new(None);
+/// Loopback server the players of the embedded mode read the stream from,
+/// so that the picture and the sound come from a single upstream
+static PLAYBACK: LazyLock<Option<String>> = LazyLock::new(|| {
+    let server = Server::http("127.0.0.1:0")
+        .map_err(|e| eprintln!("Failed to start the playback server: {}", e))
+        .ok()?;
+    let base = format!("http://{}", server.server_addr().to_ip()?);
+    serve(Arc::new(server), ServerConfig::default(), playback_route);
+    Some(base)
+});
+
+/// Local HTTP server relaying the streams to other devices
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub enabled: bool,
+    /// Use 0.0.0.0 to reach the server from other devices
+    pub address: String,
+    pub port: u16,
+    /// Sent upstream when the playlist does not give one for the channel
+    pub user_agent: Option<String>,
+    pub hdhomerun: HdHomeRunConfig,
+    pub api: ApiConfig,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: "127.0.0.1".to_string(),
+            port: 8555,
+            user_agent: None,
+            hdhomerun: HdHomeRunConfig::default(),
+            api: ApiConfig::default(),
+        }
+    }
+}
+
+/// Visible channels, in the order of the interface but without the locked
+/// ones, rebuilt once the playlist or the user state changed
+pub fn channels() -> Arc<Vec<Channel>> {
+    let revision = store::revision();
+    let mut cache = CHANNELS.lock().unwrap();
+    if let Some((built, channels)) = cache.as_ref() {
+        if *built == revision {
+            return channels.clone();
+        }
+    }
+
+    let channels: Arc<Vec<Channel>> = Arc::new(
+        UserState::load()
+            .apply(&store::groups(), false, true)
+            .into_iter()
+            .flat_map(|g| g.channels)
+            .collect(),
+    );
+    *cache = Some((revision, channels.clone()));
+    channels
+}
+
+/// Starts the server in the background, replacing the running one
+pub fn start(config: &ServerConfig) -> Result<()> {
+    stop();
+    let server = Server::http((config.address.as_str(), config.port)).map_err(|e| e.to_string())?;
+    let server = Arc::new(server);
+    *SERVER.lock().unwrap() = Some(server.clone());
+    println!("Serving streams on {}:{}", config.address, config.port);
+    serve(server, config.clone(), route);
+    Ok(())
+}
+
+/// Answers the requests of the server on a thread each
+fn serve(
+    server: Arc<Server>,
+    config: ServerConfig,
+    route: fn(&Request, &ServerConfig) -> ResponseBox,
+) {
+    std::thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let config = config.clone();
+            std::thread::spawn(move || {
+                let response = route(&request, &config);
+                if let Err(e) = request.respond(response) {
+                    eprintln!("Failed to answer a request: {}", e);
+                }
+            });
+        }
+    });
+}
+
+pub fn stop() {
+    if let Some(server) = SERVER.lock().unwrap().take() {
+        server.unblock();
+    }
+}
+
+fn route(request: &Request, config: &ServerConfig) -> ResponseBox {
+    let (path, query) = request.url().split_once('?').unwrap_or((request.url(), ""));
+    let base = base_url(request, config);
+
+    match path {
+        "/playlist.m3u" => {
+            let playlist = to_m3u(&selected_channels(query));
+            return reply(200, "audio/x-mpegurl", with_guide(playlist, &base, query));
+        }
+        "/streams.m3u" => {
+            let playlist = proxied_playlist(&base, query);
+            return reply(200, "audio/x-mpegurl", with_guide(playlist, &base, query));
+        }
+        "/epg.xml" => {
+            let guide = curated_epg(&Config::load(), &selected_channels(query));
+            return stream_response("application/xml", guide);
+        }
+        _ => {}
+    }
+    if let Some(id) = path.strip_prefix("/stream/") {
+        return match channels().iter().find(|c| stream_id(c) == id) {
+            Some(channel) => proxy(channel, query, config),
+            None => reply(404, "text/plain", "Unknown channel".to_string()),
+        };
+    }
+    if config.api.enabled {
+        if path == "/" || path == "/remote" {
+            return reply(200, "text/html; charset=utf-8", api::remote_page());
+        }
+        if path.starts_with("/api/") {
+            return api::route(request, path, query, &config.api);
+        }
+    }
+    if config.hdhomerun.enabled {
+        if let Some(response) = hdhomerun::route(path, &base, config) {
+            return response;
+        }
+    }
+    reply(404, "text/plain", "Not found".to_string())
+}
+
+pub fn reply(status: u16, content_type: &str, body: String) -> ResponseBox {
+    Response::from_string(body)
+        .with_status_code(status)
+        .with_header(header("Content-Type", content_type))
+        .boxed()
+}
+
+pub fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).unwrap()
+}
+
+/// Address the client used to reach the server
+pub fn base_url(request: &Request, config: &ServerConfig) -> String {
+    let host = request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Host"))
+        .map(|h| h.value.to_string())
+        .unwrap_or_else(|| format!("{}:{}", config.address, config.port));
+    format!("http://{}", host)
+}
+
+/// Stable identifier of a channel in URLs, a FNV-1a hash of its key
+pub fn stream_id(channel: &Channel) -> String {
+    hash(&channel.key())
+}
+
+/// FNV-1a hash of a text, as 16 hex digits
+pub fn hash(value: &str) -> String {
+    let hash = value.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// Compares two secrets in a time that does not tell where they differ
+pub fn same_secret(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// URL of the channel stream on this server
+pub fn stream_url(base: &str, channel: &Channel) -> String {
+    format!("{}/stream/{}", base, stream_id(channel))
+}
+
+fn mac(channel: &Channel, upstream: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(SIGNING_KEY.as_slice()).unwrap();
+    mac.update(stream_id(channel).as_bytes());
+    mac.update(b"\n");
+    mac.update(upstream.as_bytes());
+    mac
+}
+
+fn proxied_url(base: &str, channel: &Channel, upstream: &str) -> String {
+    let signature: String = mac(channel, upstream)
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    let upstream: String = form_urlencoded::byte_serialize(upstream.as_bytes()).collect();
+    format!(
+        "{}?url={}&sig={}",
+        stream_url(base, channel),
+        upstream,
+        signature
+    )
+}
+
+/// Whether the proxy handed out this upstream URL for the channel
+fn is_signed(channel: &Channel, upstream: &str, signature: &str) -> bool {
+    let bytes: Option<Vec<u8>> = (0..signature.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(signature.get(i..i + 2)?, 16).ok())
+        .collect();
+    bytes.is_some_and(|bytes| mac(channel, upstream).verify_slice(&bytes).is_ok())
+}
+
+/// Visible channels, restricted to a group when the query names one
+fn selected_channels(query: &str) -> Vec<Channel> {
+    let group = form_urlencoded::parse(query.as_bytes())
+        .find(|(key, _)| key == "group")
+        .map(|(_, value)| value.into_owned());
+    let Some(group) = group else {
+        return channels().to_vec();
+    };
+
+    let state = UserState::load();
+    channels()
+        .iter()
+        .filter(|c| c.group == group || (group == FAVORITES_GROUP && state.is_favorite(c)))
+        .cloned()
+        .collect()
+}
+
+/// Points players to the guide matching the playlist
+fn with_guide(playlist: String, base: &str, query: &str) -> String {
+    let guide = match query {
+        "" => format!("{}/epg.xml", base),
+        query => format!("{}/epg.xml?{}", base, query),
+    };
+    playlist.replacen("#EXTM3U", &format!("#EXTM3U url-tvg=\"{}\"", guide), 1)
+}
+
+fn proxied_playlist(base: &str, query: &str) -> String {
+    let channels: Vec<Channel> = selected_channels(query)
+        .into_iter()
+        .map(|mut channel| {
+            let proxied = channel.clone();
+            for variant in channel.variants.iter_mut() {
+                variant.url = proxied_url(base, &proxied, &variant.url);
+            }
+            channel.url = stream_url(base, &proxied);
+            channel
+        })
+        .collect();
+    to_m3u(&channels)
+}
+
+/// Only relays the channel being played, which may be locked for the
+/// clients of the other server
+fn playback_route(request: &Request, _config: &ServerConfig) -> ResponseBox {
+    let (path, query) = request.url().split_once('?').unwrap_or((request.url(), ""));
+    let playing = now_playing()
+        .map(|now_playing| now_playing.channel)
+        .filter(|channel| path.strip_prefix("/stream/") == Some(stream_id(channel).as_str()));
+    match playing {
+        Some(channel) => proxy(&channel, query, &Config::load().server),
+        None => reply(404, "text/plain", "Not playing".to_string()),
+    }
+}
+
+/// URL of a stream of the channel on the playback server
+pub fn playback_url(channel: &Channel, stream: &str) -> Option<String> {
+    let base = PLAYBACK.as_deref()?;
+    Some(proxied_url(base, channel, stream))
+}
+
+/// Headers the provider expects, as given by the playlist
+pub fn upstream_headers(channel: &Channel, config: &ServerConfig) -> Vec<(&'static str, String)> {
+    let attribute = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|name| channel.attributes.get(*name))
+            .cloned()
+    };
+
+    let mut headers = Vec::new();
+    if let Some(agent) = attribute(&["http-user-agent", "user-agent"]).or(config.user_agent.clone())
+    {
+        headers.push(("User-Agent", agent));
+    }
+    if let Some(referer) = attribute(&["http-referrer", "http-referer", "referrer", "referer"]) {
+        headers.push(("Referer", referer));
+    }
+    headers
+}
+
+/// Relays the channel stream, or one of the resources its HLS playlists
+/// point to when the query gives an upstream URL signed by the proxy; the
+/// clients of a resource share a single upstream request
+pub fn proxy(channel: &Channel, query: &str, config: &ServerConfig) -> ResponseBox {
+    let parameter = |name: &str| {
+        form_urlencoded::parse(query.as_bytes())
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+    };
+    let upstream = match parameter("url") {
+        None => channel.url.clone(),
+        // Variants stay reachable after a restart changed the signing key
+        Some(url) if channel.streams().any(|stream| stream == url) => url,
+        Some(url) if parameter("sig").is_some_and(|sig| is_signed(channel, &url, &sig)) => url,
+        Some(_) => return reply(403, "text/plain", "Unknown resource".to_string()),
+    };
+
+    let pending = match relay::join(&upstream, &upstream_headers(channel, config)) {
+        Joined::Reader(reader) => return stream_response(&reader.content_type(), reader),
+        Joined::First(pending) => pending,
+    };
+    let response = match open_upstream(channel, &upstream, config) {
+        Ok(response) => response,
+        Err(e) => return reply(502, "text/plain", e.to_string()),
+    };
+
+    let content_type = content_type(&response);
+    let final_url = response.url().clone();
+    let is_hls =
+        content_type.to_lowercase().contains("mpegurl") || final_url.path().ends_with(".m3u8");
+
+    if is_hls {
+        let mut content = String::new();
+        if let Err(e) = response.take(1 << 20).read_to_string(&mut content) {
+            return reply(502, "text/plain", e.to_string());
+        }
+        return reply(
+            200,
+            &content_type,
+            rewrite_hls(&content, &final_url, channel),
+        );
+    }
+
+    let length = response.content_length();
+    stream_response(
+        &content_type,
+        pending.start(content_type.clone(), length, response),
+    )
+}
+
+/// Requests an upstream resource of the channel with its headers
+pub fn open_upstream(
+    channel: &Channel,
+    url: &str,
+    config: &ServerConfig,
+) -> reqwest::Result<reqwest::blocking::Response> {
+    let mut request = CLIENT.get(url);
+    for (name, value) in upstream_headers(channel, config) {
+        request = request.header(name, value);
+    }
+    request.send().and_then(|r| r.error_for_status())
+}
+
+pub fn content_type(response: &reqwest::blocking::Response) -> String {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("application/octet-stream")
+        .to_string()
+}
+
+/// Streams the body for as long as the client reads it
+pub fn stream_response(content_type: &str, body: impl Read + Send + 'static) -> ResponseBox {
+    Response::new(
+        200.into(),
+        vec![header("Content-Type", content_type)],
+        Box::new(body) as Box<dyn Read + Send>,
+        None,
+        None,
+    )
+}
+
+/// Reader keeping a value alive until the response is dropped
+pub struct Holding<R, T> {
+    pub reader: R,
+    pub held: T,
+}
+
+impl<R: Read, T> Read for Holding<R, T> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.reader.read(buf)
+    }
+}
+
+/// Points the URIs of an HLS playlist back to the proxy so the segments are
+/// fetched with the same headers
+fn rewrite_hls(content: &str, base: &Url, channel: &Channel) -> String {
+    let rewrite = |uri: &str| {
+        let absolute = base
+            .join(uri)
+            .map(|u| u.to_string())
+            .unwrap_or_else(|_| uri.to_string());
+        proxied_url("", channel, &absolute)
+    };
+
+    content
+        .lines()
+        .map(|line| {
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                line.to_string()
+            } else if trimmed.starts_with('#') {
+                URI_ATTRIBUTE
+                    .replace_all(line, |caps: &regex::Captures| {
+                        format!("URI=\"{}\"", rewrite(&caps[1]))
+                    })
+                    .into_owned()
+            } else {
+                rewrite(trimmed)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::m3u::group_channels;
+    use std::sync::OnceLock;
+
+    const MEDIA_PLAYLIST: &str = "#EXTM3U
+#EXT-X-TARGETDURATION:6
+#EXT-X-KEY:METHOD=AES-128,URI=\"key.bin\"
+#EXTINF:6.0,
+segment1.ts";
+
+    /// Provider serving the streams and local server relaying them, both on
+    /// localhost
+    pub struct Fixture {
+        pub upstream: String,
+        pub local: String,
+        pub channels: Vec<Channel>,
+    }
+
+    /// Started once, as the playlist is shared by every test through the store
+    pub fn fixture() -> &'static Fixture {
+        static FIXTURE: OnceLock<Fixture> = OnceLock::new();
+        FIXTURE.get_or_init(|| {
+            let upstream = Server::http("127.0.0.1:0").unwrap();
+            let upstream_url = format!("http://{}", upstream.server_addr().to_ip().unwrap());
+            std::thread::spawn(move || {
+                for request in upstream.incoming_requests() {
+                    let response = match request.url() {
+                        "/live/index.m3u8" => reply(
+                            200,
+                            "application/vnd.apple.mpegurl",
+                            MEDIA_PLAYLIST.to_string(),
+                        ),
+                        "/live/segment1.ts" => reply(200, "video/mp2t", "segment".to_string()),
+                        "/live/key.bin" => {
+                            reply(200, "application/octet-stream", "key".to_string())
+                        }
+                        "/private" => reply(200, "text/plain", "private".to_string()),
+                        _ => reply(404, "text/plain", String::new()),
+                    };
+                    let _ = request.respond(response);
+                }
+            });
+
+            let channels = vec![
+                Channel::fixture("Info", "News", &format!("{}/live/index.m3u8", upstream_url)),
+                Channel::fixture(
+                    "Sport",
+                    "Sports",
+                    &format!("{}/live/segment1.ts", upstream_url),
+                ),
+            ];
+            store::publish(Arc::new(group_channels(channels.clone())));
+
+            let mut config = ServerConfig::default();
+            config.hdhomerun.enabled = true;
+            let local = Server::http("127.0.0.1:0").unwrap();
+            let local_url = format!("http://{}", local.server_addr().to_ip().unwrap());
+            serve(Arc::new(local), config, route);
+
+            Fixture {
+                upstream: upstream_url,
+                local: local_url,
+                channels,
+            }
+        })
+    }
+
+    pub fn get(url: &str) -> (u16, String) {
+        let response = reqwest::blocking::get(url).unwrap();
+        (response.status().as_u16(), response.text().unwrap())
+    }
+
+    #[test]
+    fn playlist_lists_the_channels_with_their_guide() {
+        let fixture = fixture();
+        let (status, playlist) = get(&format!("{}/playlist.m3u", fixture.local));
+        assert_eq!(status, 200);
+        assert!(playlist.starts_with(&format!("#EXTM3U url-tvg=\"{}/epg.xml\"", fixture.local)));
+        for channel in &fixture.channels {
+            assert!(playlist.contains(&format!(",{}\n{}\n", channel.name, channel.url)));
+        }
+
+        let (_, news) = get(&format!("{}/playlist.m3u?group=News", fixture.local));
+        assert!(news.contains(",Info\n"));
+        assert!(!news.contains(",Sport\n"));
+    }
+
+    #[test]
+    fn stream_relays_the_channel() {
+        let fixture = fixture();
+        let sport = &fixture.channels[1];
+        assert_eq!(
+            get(&stream_url(&fixture.local, sport)),
+            (200, "segment".to_string())
+        );
+        assert_eq!(
+            get(&format!("{}/stream/0000000000000000", fixture.local)).0,
+            404
+        );
+    }
+
+    #[test]
+    fn hls_playlists_point_back_to_the_proxy() {
+        let fixture = fixture();
+        let info = &fixture.channels[0];
+        let (status, playlist) = get(&stream_url(&fixture.local, info));
+        assert_eq!(status, 200);
+
+        let prefix = format!("/stream/{}?url=", stream_id(info));
+        let segment = playlist.lines().last().unwrap();
+        assert!(segment.starts_with(&prefix));
+        assert!(segment.contains("&sig="));
+        assert!(playlist.contains(&format!("URI=\"{}", prefix)));
+
+        let key = URI_ATTRIBUTE.captures(&playlist).unwrap()[1].to_string();
+        assert_eq!(
+            get(&format!("{}{}", fixture.local, segment)),
+            (200, "segment".to_string())
+        );
+        assert_eq!(
+            get(&format!("{}{}", fixture.local, key)),
+            (200, "key".to_string())
+        );
+    }
+
+    #[test]
+    fn proxy_only_relays_the_urls_it_signed() {
+        let fixture = fixture();
+        let info = &fixture.channels[0];
+        let private = format!("{}/private", fixture.upstream);
+
+        let unsigned: String = form_urlencoded::byte_serialize(private.as_bytes()).collect();
+        let unsigned = format!("{}?url={}", stream_url(&fixture.local, info), unsigned);
+        assert_eq!(get(&unsigned).0, 403);
+        assert_eq!(get(&format!("{}&sig={}", unsigned, "00".repeat(32))).0, 403);
+
+        // Signed for another channel
+        let sport = &fixture.channels[1];
+        let signed = proxied_url(&fixture.local, sport, &private);
+        let sig = signed.split("&sig=").last().unwrap();
+        assert_eq!(get(&format!("{}&sig={}", unsigned, sig)).0, 403);
+        assert_eq!(get(&signed), (200, "private".to_string()));
+    }
+
+    #[test]
+    fn playback_server_only_relays_the_channel_being_played() {
+        let sport = &fixture().channels[1];
+        let url = playback_url(sport, &sport.url).unwrap();
+        assert!(url.starts_with("http://127.0.0.1:"));
+        assert_eq!(get(&url).0, 404);
+    }
+}
@@ -0,0 +1,221 @@
+use crate::config::{load_json, save_json, Config};
+use crate::hls::VariantChoice;
+use crate::m3u::{group_channels, Channel, Group};
+use crate::mpv::PlayerPreferences;
+use crate::parental::ParentalConfig;
+use crate::profile;
+use crate::store;
+use crate::types::Result;
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Mutex;
+
+const STATE_FILE: &str = "state.json";
+
+/// Internal name of the favorite channels, shown translated
+pub const FAVORITES_GROUP: &str = "rustream:favorites";
+/// Internal name of the recently watched channels, shown translated
+pub const RECENT_GROUP: &str = "rustream:recent";
+
+/// Held while the state file is read and written back, so that the changes
+/// made at the same time by the views, the player and the server are all kept
+static UPDATING: Mutex<()> = Mutex::new(());
+
+/// Channels kept in the watch history
+const HISTORY_LIMIT: usize = 30;
+
+/// User data attached to channels by their key, stored apart from the
+/// playlists so that it survives their refreshes, in a file of each profile
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserState {
+    pub favorites: BTreeSet<String>,
+    pub edits: BTreeMap<String, ChannelEdit>,
+    pub hidden_groups: BTreeSet<String>,
+    pub group_order: Vec<String>,
+    /// Channel keys of each group, in the order chosen by the user
+    pub channel_order: BTreeMap<String, Vec<String>>,
+    /// HLS variant chosen for the channels that should not use the best one
+    pub variants: BTreeMap<String, VariantChoice>,
+    /// Groups and channel keys hidden behind the parental PIN
+    pub locked_groups: BTreeSet<String>,
+    pub locked_channels: BTreeSet<String>,
+    /// Keys of the channels played, the latest first
+    pub history: Vec<String>,
+    pub player: PlayerPreferences,
+}
+
+/// Changes made to a channel in the editor
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChannelEdit {
+    pub name: Option<String>,
+    pub group: Option<String>,
+    pub hidden: bool,
+}
+
+impl UserState {
+    pub fn load() -> Self {
+        load_json(&profile::file(STATE_FILE))
+    }
+
+    fn save(&self) -> Result<()> {
+        save_json(&profile::file(STATE_FILE), self)?;
+        store::touch();
+        Ok(())
+    }
+
+    /// Makes a change to the saved state, read again so that the other
+    /// changes made meanwhile are kept, and returns the state saved
+    pub fn update(change: impl FnOnce(&mut Self)) -> Result<Self> {
+        let _updating = UPDATING.lock().unwrap();
+        let mut state = Self::load();
+        change(&mut state);
+        state.save()?;
+        Ok(state)
+    }
+
+    pub fn is_favorite(&self, channel: &Channel) -> bool {
+        self.favorites.contains(&channel.key())
+    }
+
+    pub fn toggle_favorite(&mut self, channel: &Channel) {
+        let key = channel.key();
+        if !self.favorites.remove(&key) {
+            self.favorites.insert(key);
+        }
+    }
+
+    pub fn record_watched(&mut self, channel: &Channel) {
+        let key = channel.key();
+        self.history.retain(|k| *k != key);
+        self.history.insert(0, key);
+        self.history.truncate(HISTORY_LIMIT);
+    }
+
+    pub fn edit(&self, channel: &Channel) -> ChannelEdit {
+        self.edits.get(&channel.key()).cloned().unwrap_or_default()
+    }
+
+    pub fn set_edit(&mut self, channel: &Channel, edit: ChannelEdit) {
+        if edit == ChannelEdit::default() {
+            self.edits.remove(&channel.key());
+        } else {
+            self.edits.insert(channel.key(), edit);
+        }
+    }
+
+    pub fn variant_choice(&self, channel: &Channel) -> Option<VariantChoice> {
+        self.variants.get(&channel.key()).copied()
+    }
+
+    pub fn set_variant_choice(&mut self, channel: &Channel, choice: Option<VariantChoice>) {
+        match choice {
+            Some(choice) => self.variants.insert(channel.key(), choice),
+            None => self.variants.remove(&channel.key()),
+        };
+    }
+
+    pub fn is_locked(&self, channel: &Channel, parental: &ParentalConfig) -> bool {
+        self.locked_channels.contains(&channel.key())
+            || self.locked_groups.contains(&channel.group)
+            || parental.matches_keywords(channel)
+    }
+
+    /// Applies the user edits on top of the loaded playlists, hidden groups
+    /// and channels are kept when `include_hidden` is set for the editor;
+    /// locked ones are left out until the parental PIN is entered, and
+    /// always when `exported` to the server
+    pub fn apply(&self, groups: &[Group], include_hidden: bool, exported: bool) -> Vec<Group> {
+        let parental = Config::load().parental;
+        let restricted = if exported {
+            parental.is_restricted_for_export()
+        } else {
+            parental.is_restricted()
+        };
+        // Other profiles add their locks to those of the main one
+        let main: Option<UserState> = profile::current()
+            .filter(|_| restricted)
+            .map(|_| load_json(STATE_FILE));
+        let channels: Vec<Channel> = groups
+            .iter()
+            .flat_map(|g| &g.channels)
+            .filter_map(|channel| {
+                let edit = self.edit(channel);
+                if edit.hidden && !include_hidden {
+                    return None;
+                }
+                let mut channel = channel.clone();
+                if let Some(name) = edit.name {
+                    channel.name = name;
+                }
+                if let Some(group) = edit.group {
+                    channel.group = group;
+                }
+                let locked = self.is_locked(&channel, &parental)
+                    || main
+                        .as_ref()
+                        .is_some_and(|m| m.is_locked(&channel, &parental));
+                if restricted && locked {
+                    return None;
+                }
+                Some(channel)
+            })
+            .collect();
+
+        let mut groups = group_channels(channels);
+        if !include_hidden {
+            groups.retain(|g| !self.hidden_groups.contains(&g.name));
+        }
+        for group in groups.iter_mut() {
+            if let Some(order) = self.channel_order.get(&group.name) {
+                group.channels.sort_by_key(|c| position(order, &c.key()));
+            }
+        }
+        groups.sort_by_key(|g| position(&self.group_order, &g.name));
+        groups
+    }
+
+    pub fn favorites_group(&self, groups: &[Group]) -> Option<Group> {
+        let channels: Vec<Channel> = groups
+            .iter()
+            .flat_map(|g| g.channels.iter())
+            .filter(|c| self.is_favorite(c))
+            .cloned()
+            .collect();
+
+        if channels.is_empty() {
+            return None;
+        }
+        Some(Group {
+            name: FAVORITES_GROUP.to_string(),
+            channels,
+        })
+    }
+
+    /// Channels of the watch history still in the playlists, the latest first
+    pub fn recent_group(&self, groups: &[Group]) -> Option<Group> {
+        let mut channels: Vec<Channel> = groups
+            .iter()
+            .flat_map(|g| g.channels.iter())
+            .filter(|c| self.history.contains(&c.key()))
+            .cloned()
+            .collect();
+        channels.sort_by_key(|c| position(&self.history, &c.key()));
+        channels.dedup_by_key(|c| c.key());
+
+        if channels.is_empty() {
+            return None;
+        }
+        Some(Group {
+            name: RECENT_GROUP.to_string(),
+            channels,
+        })
+    }
+}
+
+/// Elements missing from the order go last, in their original order
+fn position(order: &[String], value: &str) -> usize {
+    order.iter().position(|v| v == value).unwrap_or(usize::MAX)
+}
@@ -0,0 +1,28 @@
+use crate::m3u::Group;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, LazyLock, RwLock};
+
+/// Loaded playlist, shared by the views and the local server without being
+/// copied; user edits are applied by each reader
+static GROUPS: LazyLock<RwLock<Arc<Vec<Group>>>> = LazyLock::new(Default::default);
+/// Bumped whenever the playlist, the settings or the user state change, so
+/// that what is derived from them knows when to be rebuilt
+static REVISION: AtomicU64 = AtomicU64::new(0);
+
+pub fn groups() -> Arc<Vec<Group>> {
+    GROUPS.read().unwrap().clone()
+}
+
+pub fn publish(groups: Arc<Vec<Group>>) {
+    *GROUPS.write().unwrap() = groups;
+    touch();
+}
+
+pub fn revision() -> u64 {
+    REVISION.load(Ordering::SeqCst)
+}
+
+pub fn touch() {
+    REVISION.fetch_add(1, Ordering::SeqCst);
+}
@@ -0,0 +1,107 @@
+use crate::config::{load_json, Config};
+
+use iced::theme::Palette;
+use iced::{Color, Theme};
+use serde::{Deserialize, Serialize};
+use std::sync::{LazyLock, RwLock};
+
+const THEMES_FILE: &str = "themes.json";
+
+/// Theme shown by the interface and the name it was chosen by, `None`
+/// following the light or dark preference of the system
+struct Applied {
+    name: Option<String>,
+    theme: Theme,
+}
+
+static APPLIED: LazyLock<RwLock<Applied>> = LazyLock::new(|| {
+    let name = Config::load().theme;
+    RwLock::new(Applied {
+        theme: resolve(name.as_deref()),
+        name,
+    })
+});
+
+/// Palette of the themes file, colors being written as `#rrggbb`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomTheme {
+    pub name: String,
+    pub background: String,
+    pub text: String,
+    pub primary: String,
+    pub success: String,
+    pub danger: String,
+}
+
+impl CustomTheme {
+    fn theme(&self) -> Option<Theme> {
+        let color = |value: &str| {
+            let color = Color::parse(value);
+            if color.is_none() {
+                eprintln!("Failed to parse the color {} of {}", value, self.name);
+            }
+            color
+        };
+        let palette = Palette {
+            background: color(&self.background)?,
+            text: color(&self.text)?,
+            primary: color(&self.primary)?,
+            success: color(&self.success)?,
+            danger: color(&self.danger)?,
+        };
+        Some(Theme::custom(self.name.clone(), palette))
+    }
+}
+
+/// Built-in themes followed by the ones of the themes file
+pub fn available() -> Vec<Theme> {
+    let custom: Vec<CustomTheme> = load_json(THEMES_FILE);
+    Theme::ALL
+        .iter()
+        .cloned()
+        .chain(custom.iter().filter_map(CustomTheme::theme))
+        .collect()
+}
+
+fn system() -> Theme {
+    match dark_light::detect() {
+        dark_light::Mode::Light => Theme::Light,
+        dark_light::Mode::Dark | dark_light::Mode::Default => Theme::Dark,
+    }
+}
+
+fn resolve(name: Option<&str>) -> Theme {
+    let Some(name) = name else {
+        return system();
+    };
+    available()
+        .into_iter()
+        .find(|theme| theme.to_string() == name)
+        .unwrap_or_else(|| {
+            eprintln!("Failed to find the theme {}", name);
+            Theme::Dark
+        })
+}
+
+pub fn current() -> Theme {
+    APPLIED.read().unwrap().theme.clone()
+}
+
+/// Applies a theme by its name, `None` following the system preference
+pub fn set(name: Option<String>) {
+    *APPLIED.write().unwrap() = Applied {
+        theme: resolve(name.as_deref()),
+        name,
+    };
+}
+
+pub fn follows_system() -> bool {
+    APPLIED.read().unwrap().name.is_none()
+}
+
+/// Follows a change of the system preference
+pub fn refresh_system() {
+    if follows_system() {
+        APPLIED.write().unwrap().theme = system();
+    }
+}
@@ -1,29 +1,402 @@
-use crate::views::SettingsView;
+use crate::api::zap;
+use crate::config::Config;
+use crate::embedded;
+use crate::events;
+use crate::gamepad;
+use crate::keymap::{self, Action, Keymap};
+use crate::mpv::{self, now_playing, play_channel, set_paused, PlaybackEvent};
+use crate::playlist::{load_sources, refresh_sources, PlaylistDiff, PlaylistSource};
+use crate::server;
+use crate::state::UserState;
+use crate::store;
+use crate::t;
+use crate::theme;
+use crate::views::iced_utils::{GRID_COLUMNS, GRID_SCROLLABLE, SEARCH_INPUT};
 use crate::views::View;
 use crate::views::ViewMessage;
-use iced::{Element, Task};
+use crate::views::{home, Navigation, ProfileView, SettingsView};
+use iced::keyboard::{self, Key};
+use iced::widget::scrollable::{self, AbsoluteOffset, RelativeOffset};
+use iced::widget::{button, image, text, text_input, Column, Container, Row};
+use iced::{time, Alignment, Element, Length, Subscription, Task, Theme};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Delay after the last digit before the typed channel number is tuned
+const NUMBER_DELAY: Duration = Duration::from_millis(1500);
+const NUMBER_DIGITS: usize = 4;
 
 pub struct App {
     current_view: Box<dyn View>,
+    /// Scroll offset of the grid of the current view
+    scroll: AbsoluteOffset,
+    /// Views Back returns to, with their scroll offsets
+    history: Vec<(Box<dyn View>, AbsoluteOffset)>,
+    notice: Option<String>,
+    refreshing: bool,
+    checked: usize,
+    /// Channel number being typed
+    number: String,
+    number_typed_at: Instant,
+    /// Latest picture of the embedded playback
+    frame: Option<image::Handle>,
+    keymap: Keymap,
+    /// Store revision the keymaps were loaded at, to reload them once the
+    /// settings are saved
+    keymap_revision: u64,
 }
 
 impl App {
     pub fn new() -> (Self, Task<ViewMessage>) {
+        let config = Config::load();
+        let (groups, _) = load_sources(&config, |_| false);
+        store::publish(Arc::new(groups));
+        let mut notice = None;
+        if config.server.enabled {
+            if let Err(e) = server::start(&config.server) {
+                notice = Some(t!("notice-server-failed", error = e.to_string()));
+            }
+        }
+        #[cfg(target_os = "linux")]
+        crate::mpris::start();
+        if config.gamepad.enabled {
+            gamepad::start(config.gamepad.buttons.clone());
+        }
+        let current_view: Box<dyn View> = if config.sources.is_empty() {
+            Box::new(SettingsView::new())
+        } else if !config.profiles.is_empty() {
+            Box::new(ProfileView::new())
+        } else {
+            home()
+        };
+
+        let keymap = config.keymap.clone();
+        let refreshing = config.sources.iter().any(|s| s.refresh_on_startup);
+        let task = if refreshing {
+            Self::refresh(config, |s| s.refresh_on_startup)
+        } else {
+            Task::none()
+        };
+
         (
             Self {
-                current_view: Box::new(SettingsView::new(Vec::new(), None)),
+                current_view,
+                scroll: AbsoluteOffset::default(),
+                history: Vec::new(),
+                notice,
+                refreshing,
+                checked: 0,
+                number: String::new(),
+                number_typed_at: Instant::now(),
+                frame: None,
+                keymap,
+                keymap_revision: store::revision(),
             },
-            Task::none(),
+            task,
         )
     }
 
-    pub fn update(&mut self, message: ViewMessage) {
-        if let Some(new_view) = self.current_view.update(message) {
-            self.current_view = new_view;
+    fn refresh(config: Config, download: fn(&PlaylistSource) -> bool) -> Task<ViewMessage> {
+        Task::perform(
+            refresh_sources(config, download),
+            |refreshed| match refreshed {
+                Some((groups, diff)) => ViewMessage::PlaylistRefreshed(Arc::new(groups), diff),
+                None => ViewMessage::PlaylistRefreshed(
+                    store::groups(),
+                    PlaylistDiff {
+                        errors: vec![t!("playlist-refresh-failed")],
+                        ..Default::default()
+                    },
+                ),
+            },
+        )
+    }
+
+    pub fn update(&mut self, message: ViewMessage) -> Task<ViewMessage> {
+        match message {
+            ViewMessage::RefreshTick => {
+                let config = Config::load();
+                if !self.refreshing && config.sources.iter().any(|s| s.is_due()) {
+                    self.refreshing = true;
+                    return Self::refresh(config, PlaylistSource::is_due);
+                }
+                return Task::none();
+            }
+            ViewMessage::PlaylistRefreshed(ref groups, ref diff) => {
+                self.refreshing = false;
+                store::publish(groups.clone());
+                if !diff.is_empty() {
+                    self.notice = Some(diff.summary());
+                }
+            }
+            ViewMessage::FrameDecoded => {
+                self.frame = embedded::take_frame();
+                return Task::none();
+            }
+            ViewMessage::DecoderStopped(ref error) => {
+                self.frame = None;
+                let error = error.clone().unwrap_or_else(|| t!("decoder-ended"));
+                self.notice = Some(t!("notice-picture-lost", error = error));
+                return Task::none();
+            }
+            ViewMessage::Playback(ref event) => {
+                if matches!(
+                    event,
+                    PlaybackEvent::Failed { .. }
+                        | PlaybackEvent::Unavailable { .. }
+                        | PlaybackEvent::Stopped
+                ) {
+                    self.frame = None;
+                }
+                self.notice = match event {
+                    PlaybackEvent::Playing {
+                        channel,
+                        url,
+                        stream,
+                        streams,
+                    } => Some(t!(
+                        "notice-playing",
+                        channel = channel,
+                        stream = *stream,
+                        streams = *streams,
+                        url = url
+                    )),
+                    PlaybackEvent::Failed { channel, error, .. } => Some(t!(
+                        "notice-playback-failed",
+                        channel = channel,
+                        error = error
+                    )),
+                    PlaybackEvent::Unavailable { channel } => {
+                        Some(t!("notice-no-stream", channel = channel))
+                    }
+                    PlaybackEvent::Stopped => None,
+                };
+            }
+            ViewMessage::HealthChecked(..) => {
+                self.checked += 1;
+                self.notice = Some(t!("notice-health-progress", count = self.checked));
+            }
+            ViewMessage::HealthCheckFinished(alive, total) => {
+                self.checked = 0;
+                self.notice = Some(t!("notice-health-finished", alive = alive, total = total));
+            }
+            ViewMessage::DismissNotice => {
+                self.notice = None;
+                return Task::none();
+            }
+            ViewMessage::GridScrolled(offset) => {
+                self.scroll = offset;
+                return Task::none();
+            }
+            ViewMessage::KeyPressed(key) => return self.key_pressed(key),
+            ViewMessage::ActionTriggered(action) => return self.perform(action),
+            ViewMessage::SystemThemeTick => {
+                theme::refresh_system();
+                return Task::none();
+            }
+            ViewMessage::NumberTick => {
+                if !self.number.is_empty() && self.number_typed_at.elapsed() >= NUMBER_DELAY {
+                    self.tune_number();
+                }
+                return Task::none();
+            }
+            _ => {}
         }
+
+        let task = match self.current_view.update(message) {
+            Some(navigation) => self.navigate(navigation),
+            None => Task::none(),
+        };
+        self.reload_keymaps();
+        task
+    }
+
+    fn reload_keymaps(&mut self) {
+        let revision = store::revision();
+        if revision == self.keymap_revision {
+            return;
+        }
+        let config = Config::load();
+        self.keymap = config.keymap;
+        gamepad::set_buttons(config.gamepad.buttons);
+        self.keymap_revision = revision;
+    }
+
+    fn navigate(&mut self, navigation: Navigation) -> Task<ViewMessage> {
+        match navigation {
+            Navigation::Push(view) => {
+                let previous = std::mem::replace(&mut self.current_view, view);
+                self.history.push((previous, self.scroll));
+                self.scroll = AbsoluteOffset::default();
+                Task::none()
+            }
+            Navigation::Back => {
+                let Some((mut view, scroll)) = self.history.pop() else {
+                    self.current_view = home();
+                    self.scroll = AbsoluteOffset::default();
+                    return Task::none();
+                };
+                view.resume();
+                self.current_view = view;
+                self.scroll = scroll;
+                scrollable::scroll_to(GRID_SCROLLABLE.clone(), scroll)
+            }
+            Navigation::Reset(view) => {
+                self.history.clear();
+                self.current_view = view;
+                self.scroll = AbsoluteOffset::default();
+                Task::none()
+            }
+        }
+    }
+
+    fn key_pressed(&mut self, key: Key) -> Task<ViewMessage> {
+        if let Some(action) = self.keymap.action(&key) {
+            return self.perform(action);
+        }
+        if let Key::Character(c) = &key {
+            let is_digit = c.len() == 1 && c.chars().all(|c| c.is_ascii_digit());
+            if is_digit && self.number.len() < NUMBER_DIGITS {
+                self.number += c;
+                self.number_typed_at = Instant::now();
+            }
+        }
+        Task::none()
+    }
+
+    /// Runs an action of the keyboard or of a gamepad
+    fn perform(&mut self, action: Action) -> Task<ViewMessage> {
+        match action {
+            Action::Activate if !self.number.is_empty() => self.tune_number(),
+            Action::Back if !self.number.is_empty() => self.number.clear(),
+            Action::Search => return text_input::focus(SEARCH_INPUT.clone()),
+            Action::Next | Action::Previous => {
+                let offset = if action == Action::Next { 1 } else { -1 };
+                if zap(offset).is_none() {
+                    self.notice = Some(t!("notice-nothing-playing"));
+                }
+            }
+            Action::PlayPause => {
+                if let Some(now_playing) = now_playing() {
+                    set_paused(!now_playing.paused);
+                }
+            }
+            Action::Stop => mpv::stop(),
+            _ => {
+                if let Some(navigation) = self.current_view.update(ViewMessage::Navigate(action)) {
+                    return self.navigate(navigation);
+                }
+                return match self.current_view.grid_focus() {
+                    Some((index, len)) => scrollable::snap_to(
+                        GRID_SCROLLABLE.clone(),
+                        RelativeOffset {
+                            x: 0.0,
+                            y: grid_offset(index, len),
+                        },
+                    ),
+                    None => Task::none(),
+                };
+            }
+        }
+        Task::none()
+    }
+
+    /// Plays the channel of the typed number, channels being numbered from 1
+    /// in the order shown in the interface
+    fn tune_number(&mut self) {
+        let number = std::mem::take(&mut self.number);
+        let channel = number
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| n.checked_sub(1))
+            .and_then(|index| {
+                UserState::load()
+                    .apply(&store::groups(), false, false)
+                    .into_iter()
+                    .flat_map(|g| g.channels)
+                    .nth(index)
+            });
+        match channel {
+            Some(channel) => play_channel(channel),
+            None => self.notice = Some(t!("notice-no-channel-number", number = number)),
+        }
+    }
+
+    pub fn subscription(&self) -> Subscription<ViewMessage> {
+        let number_tick = (!self.number.is_empty())
+            .then(|| time::every(Duration::from_millis(250)).map(|_| ViewMessage::NumberTick));
+        let system_theme = theme::follows_system()
+            .then(|| time::every(Duration::from_secs(5)).map(|_| ViewMessage::SystemThemeTick));
+        Subscription::batch(
+            [
+                time::every(Duration::from_secs(60)).map(|_| ViewMessage::RefreshTick),
+                Subscription::run(events::messages),
+                keyboard::on_key_press(keymap::key_pressed),
+            ]
+            .into_iter()
+            .chain(number_tick)
+            .chain(system_theme),
+        )
+    }
+
+    pub fn theme(&self) -> Theme {
+        theme::current()
     }
 
     pub fn view(&self) -> Element<ViewMessage> {
-        self.current_view.view()
+        let notice = self.notice.as_ref().map(|notice| {
+            Container::new(
+                Row::new()
+                    .spacing(10)
+                    .align_y(Alignment::Center)
+                    .push(text(notice).size(14).width(Length::Fill))
+                    .push(button(text(t!("ok"))).on_press(ViewMessage::DismissNotice)),
+            )
+            .padding(10)
+        });
+
+        let number = (!self.number.is_empty()).then(|| {
+            Container::new(text(t!("channel-number", number = self.number.as_str())).size(24))
+                .padding(10)
+        });
+
+        // The current view becomes a side panel of the embedded playback
+        let content: Element<ViewMessage> = match &self.frame {
+            Some(frame) => Row::new()
+                .push(
+                    Column::new()
+                        .spacing(10)
+                        .padding(10)
+                        .width(Length::FillPortion(3))
+                        .push(
+                            image(frame.clone())
+                                .width(Length::Fill)
+                                .height(Length::Fill),
+                        )
+                        .push(
+                            button(text(t!("stop")))
+                                .on_press(ViewMessage::ActionTriggered(Action::Stop))
+                                .padding(10),
+                        ),
+                )
+                .push(Container::new(self.current_view.view()).width(Length::FillPortion(2)))
+                .into(),
+            None => self.current_view.view(),
+        };
+
+        Column::new()
+            .push_maybe(notice)
+            .push_maybe(number)
+            .push(content)
+            .into()
+    }
+}
+
+/// Vertical scroll offset bringing the row of a grid button into view
+fn grid_offset(index: usize, len: usize) -> f32 {
+    let rows = len.div_ceil(GRID_COLUMNS);
+    if rows <= 1 {
+        return 0.0;
     }
+    (index / GRID_COLUMNS) as f32 / (rows - 1) as f32
 }
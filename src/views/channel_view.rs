@@ -1,39 +1,67 @@
-use super::iced_utils::create_buttons;
-use super::{GroupView, View, ViewMessage};
+use super::iced_utils::{
+    create_buttons, export_channels, move_focus, GRID_SCROLLABLE, SEARCH_INPUT,
+};
+use super::{DetailView, Navigation, View, ViewMessage};
+use crate::health::HealthResults;
+use crate::i18n::group_name;
+use crate::keymap::Action;
 use crate::m3u::{Channel, Group};
-use crate::mpv::play;
-use iced::widget::{button, scrollable, text_input, Column, Container, Row};
+use crate::mpv::play_channel;
+use crate::state::{UserState, FAVORITES_GROUP, RECENT_GROUP};
+use crate::store;
+use crate::t;
+use iced::widget::{button, checkbox, scrollable, text, text_input, Column, Container, Row};
 use iced::{Element, Length};
 use std::cmp::Ordering;
+use std::collections::BTreeSet;
 
 pub struct ChannelView {
-    m3u_filepath: Option<String>,
-    groups: Vec<Group>,
+    group_name: String,
+    state: UserState,
+    health: HealthResults,
+    hide_dead: bool,
     channels: Vec<Channel>,
     filtered_channels: Vec<Channel>,
     search_text: String,
+    selecting: bool,
+    inspecting: bool,
+    selection: BTreeSet<String>,
+    focused: Option<usize>,
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     BackToGroups,
     ChannelSelected(usize),
+    FavoriteToggled(usize),
     SearchTextChanged(String),
+    ExportView,
+    SelectionToggled,
+    InspectionToggled,
+    ExportSelection,
+    HideDeadToggled(bool),
 }
 
 impl ChannelView {
-    pub fn new(group: Group, groups: Vec<Group>, m3u_filepath: Option<String>) -> Self {
+    pub fn new(group: Group) -> Self {
         Self {
-            m3u_filepath,
-            groups,
+            group_name: group.name,
+            state: UserState::load(),
+            health: HealthResults::load(),
+            hide_dead: false,
             channels: group.channels.clone(),
             filtered_channels: group.channels,
             search_text: String::new(),
+            selecting: false,
+            inspecting: false,
+            selection: BTreeSet::new(),
+            focused: None,
         }
     }
 
     fn create_search_bar(&self) -> Element<ViewMessage> {
-        text_input("Rechercher", &self.search_text)
+        text_input(&t!("search"), &self.search_text)
+            .id(SEARCH_INPUT.clone())
             .padding(10)
             .size(20)
             .on_input(|s| ViewMessage::ChannelViewMessage(Message::SearchTextChanged(s)))
@@ -41,7 +69,7 @@ impl ChannelView {
     }
 
     fn create_back_button(&self) -> Element<ViewMessage> {
-        button("Retour")
+        button(text(t!("back")))
             .on_press(ViewMessage::ChannelViewMessage(Message::BackToGroups))
             .padding(10)
             .width(Length::Fill)
@@ -49,14 +77,96 @@ impl ChannelView {
             .into()
     }
 
+    fn create_export_bar(&self) -> Element<ViewMessage> {
+        let select_label = if self.selecting {
+            t!("selection-finish")
+        } else {
+            t!("selection-start")
+        };
+        let export_selection = button(text(t!("export-selection", count = self.selection.len())))
+            .on_press_maybe(
+                (!self.selection.is_empty())
+                    .then_some(ViewMessage::ChannelViewMessage(Message::ExportSelection)),
+            )
+            .padding(10);
+
+        Row::new()
+            .spacing(10)
+            .push(
+                button(text(t!("export-list")))
+                    .on_press(ViewMessage::ChannelViewMessage(Message::ExportView))
+                    .padding(10),
+            )
+            .push(
+                button(text(select_label))
+                    .on_press(ViewMessage::ChannelViewMessage(Message::SelectionToggled))
+                    .padding(10),
+            )
+            .push(export_selection)
+            .push(
+                button(text(if self.inspecting {
+                    t!("inspection-finish")
+                } else {
+                    t!("details")
+                }))
+                .on_press(ViewMessage::ChannelViewMessage(Message::InspectionToggled))
+                .padding(10),
+            )
+            .push(
+                checkbox(t!("hide-dead-channels"), self.hide_dead)
+                    .on_toggle(|b| ViewMessage::ChannelViewMessage(Message::HideDeadToggled(b))),
+            )
+            .into()
+    }
+
+    /// Reads the channels of the group again from the playlist and the user
+    /// state
+    fn reload(&mut self) {
+        self.state = UserState::load();
+        let edited_groups = self.state.apply(&store::groups(), false, false);
+        let group = match self.group_name.as_str() {
+            FAVORITES_GROUP => self.state.favorites_group(&edited_groups),
+            RECENT_GROUP => self.state.recent_group(&edited_groups),
+            name => edited_groups.into_iter().find(|g| g.name == name),
+        };
+        self.channels = group.map(|g| g.channels).unwrap_or_default();
+        update_filtered_list(self);
+    }
+
     fn on_press(index: usize) -> ViewMessage {
         ViewMessage::ChannelViewMessage(Message::ChannelSelected(index))
     }
 
+    fn on_right_press(index: usize) -> ViewMessage {
+        ViewMessage::ChannelViewMessage(Message::FavoriteToggled(index))
+    }
+
     fn create_ui(&self) -> Column<ViewMessage> {
         let search_bar = self.create_search_bar();
         let back_button = self.create_back_button();
-        let channels = create_buttons::<Channel>(self.filtered_channels.clone(), Self::on_press);
+        let labels: Vec<String> = self
+            .filtered_channels
+            .iter()
+            .map(|channel| {
+                let mut label = channel.name.clone();
+                if self.state.is_favorite(channel) {
+                    label = format!("★ {}", label);
+                }
+                if self.health.is_dead(channel) {
+                    label = format!("✖ {}", label);
+                }
+                if self.selection.contains(&channel.key()) {
+                    label = format!("✔ {}", label);
+                }
+                label
+            })
+            .collect();
+        let channels = create_buttons(
+            labels,
+            Self::on_press,
+            Some(Self::on_right_press),
+            self.focused,
+        );
 
         Column::new()
             .spacing(20)
@@ -65,9 +175,12 @@ impl ChannelView {
                     .padding(10)
                     .center_x(Length::Fill),
             )
+            .push(Container::new(self.create_export_bar()).padding(10))
             .push(
                 Container::new(
                     scrollable(channels)
+                        .id(GRID_SCROLLABLE.clone())
+                        .on_scroll(|viewport| ViewMessage::GridScrolled(viewport.absolute_offset()))
                         .height(Length::Fill)
                         .width(Length::Fill),
                 )
@@ -77,26 +190,84 @@ impl ChannelView {
 }
 
 impl View for ChannelView {
-    fn update(&mut self, message: ViewMessage) -> Option<Box<dyn View>> {
+    fn update(&mut self, message: ViewMessage) -> Option<Navigation> {
         match message {
             ViewMessage::ChannelViewMessage(msg) => match msg {
                 Message::BackToGroups => {
-                    return Some(Box::new(GroupView::new(
-                        self.groups.clone(),
-                        self.m3u_filepath.clone(),
-                    )));
+                    return Some(Navigation::Back);
+                }
+                Message::ChannelSelected(index) if self.selecting => {
+                    let key = self.filtered_channels[index].key();
+                    if !self.selection.remove(&key) {
+                        self.selection.insert(key);
+                    }
+                }
+                Message::ChannelSelected(index) if self.inspecting => {
+                    return Some(Navigation::Push(Box::new(DetailView::new(
+                        self.filtered_channels[index].clone(),
+                    ))));
                 }
                 Message::ChannelSelected(index) => {
-                    let selected_channel = self.filtered_channels[index].clone();
-                    println!("Chaîne sélectionnée : {}", selected_channel.name);
-
-                    play(selected_channel);
+                    play_channel(self.filtered_channels[index].clone());
+                }
+                Message::FavoriteToggled(index) => {
+                    let channel = &self.filtered_channels[index];
+                    match UserState::update(|state| state.toggle_favorite(channel)) {
+                        Ok(state) => self.state = state,
+                        Err(e) => eprintln!("Failed to save favorites: {}", e),
+                    }
                 }
                 Message::SearchTextChanged(new_text) => {
                     self.search_text = new_text;
+                    self.focused = None;
+                    update_filtered_list(self);
+                }
+                Message::ExportView => {
+                    export_channels(&self.filtered_channels, &group_name(&self.group_name));
+                }
+                Message::SelectionToggled => {
+                    self.selecting = !self.selecting;
+                    self.inspecting = false;
+                }
+                Message::InspectionToggled => {
+                    self.inspecting = !self.inspecting;
+                    self.selecting = false;
+                }
+                Message::ExportSelection => {
+                    let selection: Vec<Channel> = self
+                        .channels
+                        .iter()
+                        .filter(|c| self.selection.contains(&c.key()))
+                        .cloned()
+                        .collect();
+                    export_channels(&selection, &t!("export-selection-name"));
+                }
+                Message::HideDeadToggled(hide) => {
+                    self.hide_dead = hide;
                     update_filtered_list(self);
                 }
             },
+            ViewMessage::Navigate(Action::Activate) => {
+                let index = self.focused.unwrap_or_default();
+                if index < self.filtered_channels.len() {
+                    return self.update(ViewMessage::ChannelViewMessage(Message::ChannelSelected(
+                        index,
+                    )));
+                }
+            }
+            ViewMessage::Navigate(Action::Back) => {
+                return self.update(ViewMessage::ChannelViewMessage(Message::BackToGroups));
+            }
+            ViewMessage::Navigate(action) => {
+                self.focused = move_focus(self.focused, self.filtered_channels.len(), action);
+            }
+            ViewMessage::HealthChecked(key, report) => {
+                self.health.reports.insert(key, report);
+                if self.hide_dead {
+                    update_filtered_list(self);
+                }
+            }
+            ViewMessage::PlaylistRefreshed(..) => self.reload(),
             _ => {}
         }
         None
@@ -108,21 +279,35 @@ impl View for ChannelView {
             .center_x(Length::Fill)
             .into()
     }
+
+    fn resume(&mut self) {
+        self.health = HealthResults::load();
+        self.reload();
+    }
+
+    fn grid_focus(&self) -> Option<(usize, usize)> {
+        Some((self.focused?, self.filtered_channels.len()))
+    }
 }
 
 fn update_filtered_list(state: &mut ChannelView) {
+    let channels: Vec<Channel> = state
+        .channels
+        .iter()
+        .filter(|channel| !state.hide_dead || !state.health.is_dead(channel))
+        .cloned()
+        .collect();
+
     if state.search_text.is_empty() {
-        state.filtered_channels = state.channels.clone();
+        state.filtered_channels = channels;
     } else {
         let search_lower = state.search_text.to_lowercase().replace(' ', "");
-        let mut filtered: Vec<_> = state
-            .channels
-            .iter()
+        let mut filtered: Vec<_> = channels
+            .into_iter()
             .filter(|channel| {
                 let channel_name_lower = channel.name.to_lowercase().replace(' ', "");
                 search_lower.chars().all(|c| channel_name_lower.contains(c))
             })
-            .cloned()
             .collect();
 
         filtered.sort_by(|a, b| {
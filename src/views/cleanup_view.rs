@@ -0,0 +1,213 @@
+use super::{Navigation, View, ViewMessage};
+use crate::cleanup::{Cleaner, CleanupRule};
+use crate::config::Config;
+use crate::keymap::Action;
+use crate::m3u::Group;
+use crate::store;
+use crate::t;
+
+use iced::widget::{button, checkbox, scrollable, text, text_input, Column, Container, Row};
+use iced::{Alignment, Element, Length};
+use regex::Regex;
+use std::sync::Arc;
+
+const PREVIEW_LIMIT: usize = 500;
+
+pub struct CleanupView {
+    config: Config,
+    groups: Arc<Vec<Group>>,
+    preview: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    BackToSettings,
+    EnabledToggled(bool),
+    StripQualityToggled(bool),
+    StripSymbolsToggled(bool),
+    PatternChanged(usize, String),
+    ReplacementChanged(usize, String),
+    RemoveRule(usize),
+    AddRule,
+}
+
+impl CleanupView {
+    pub fn new() -> Self {
+        let mut view = Self {
+            config: Config::load(),
+            groups: store::groups(),
+            preview: Vec::new(),
+        };
+        view.update_preview();
+        view
+    }
+
+    fn update_preview(&mut self) {
+        let cleaner = Cleaner::new(&self.config.cleanup);
+        self.preview = self
+            .groups
+            .iter()
+            .flat_map(|g| g.channels.iter())
+            .filter_map(|channel| {
+                let (name, quality) = cleaner.clean(&channel.original_name);
+                if name == channel.original_name && quality.is_none() {
+                    return None;
+                }
+                Some(match quality {
+                    Some(quality) => format!("{} → {} [{}]", channel.original_name, name, quality),
+                    None => format!("{} → {}", channel.original_name, name),
+                })
+            })
+            .take(PREVIEW_LIMIT)
+            .collect();
+    }
+
+    fn save(&mut self) {
+        if let Err(e) = self.config.save() {
+            eprintln!("Failed to save settings: {}", e);
+        }
+        self.update_preview();
+    }
+
+    fn create_rule_row(&self, index: usize, rule: &CleanupRule) -> Element<ViewMessage> {
+        let status = match Regex::new(&rule.pattern) {
+            Ok(_) => String::new(),
+            Err(_) => t!("invalid"),
+        };
+
+        Row::new()
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .push(text_input(&t!("regex"), &rule.pattern).on_input(move |s| {
+                ViewMessage::CleanupViewMessage(Message::PatternChanged(index, s))
+            }))
+            .push(
+                text_input(&t!("replacement"), &rule.replacement).on_input(move |s| {
+                    ViewMessage::CleanupViewMessage(Message::ReplacementChanged(index, s))
+                }),
+            )
+            .push(text(status).width(80))
+            .push(
+                button(text(t!("remove")))
+                    .on_press(ViewMessage::CleanupViewMessage(Message::RemoveRule(index))),
+            )
+            .into()
+    }
+}
+
+impl Default for CleanupView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl View for CleanupView {
+    fn update(&mut self, message: ViewMessage) -> Option<Navigation> {
+        match message {
+            ViewMessage::CleanupViewMessage(msg) => match msg {
+                Message::BackToSettings => {
+                    let cleaner = Cleaner::new(&self.config.cleanup);
+                    let mut groups = self.groups.to_vec();
+                    for group in groups.iter_mut() {
+                        cleaner.apply(&mut group.channels);
+                    }
+                    store::publish(Arc::new(groups));
+                    return Some(Navigation::Back);
+                }
+                Message::EnabledToggled(enabled) => {
+                    self.config.cleanup.enabled = enabled;
+                    self.save();
+                }
+                Message::StripQualityToggled(strip) => {
+                    self.config.cleanup.strip_quality = strip;
+                    self.save();
+                }
+                Message::StripSymbolsToggled(strip) => {
+                    self.config.cleanup.strip_symbols = strip;
+                    self.save();
+                }
+                Message::PatternChanged(index, pattern) => {
+                    self.config.cleanup.rules[index].pattern = pattern;
+                    self.save();
+                }
+                Message::ReplacementChanged(index, replacement) => {
+                    self.config.cleanup.rules[index].replacement = replacement;
+                    self.save();
+                }
+                Message::RemoveRule(index) => {
+                    self.config.cleanup.rules.remove(index);
+                    self.save();
+                }
+                Message::AddRule => {
+                    self.config.cleanup.rules.push(CleanupRule {
+                        pattern: String::new(),
+                        replacement: String::new(),
+                    });
+                }
+            },
+            ViewMessage::Navigate(Action::Back) => {
+                return self.update(ViewMessage::CleanupViewMessage(Message::BackToSettings));
+            }
+            ViewMessage::PlaylistRefreshed(groups, _) => {
+                self.groups = groups;
+                self.update_preview();
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn view(&self) -> Element<ViewMessage> {
+        let cleanup = &self.config.cleanup;
+
+        let back_button = button(text(t!("back")))
+            .on_press(ViewMessage::CleanupViewMessage(Message::BackToSettings))
+            .padding(10);
+
+        let rules = cleanup
+            .rules
+            .iter()
+            .enumerate()
+            .fold(Column::new().spacing(10), |column, (index, rule)| {
+                column.push(self.create_rule_row(index, rule))
+            });
+
+        let preview = self
+            .preview
+            .iter()
+            .fold(Column::new().spacing(5), |column, line| {
+                column.push(text(line).size(14))
+            });
+
+        Container::new(
+            Column::new()
+                .spacing(20)
+                .push(back_button)
+                .push(
+                    checkbox(t!("cleanup-enabled"), cleanup.enabled)
+                        .on_toggle(|b| ViewMessage::CleanupViewMessage(Message::EnabledToggled(b))),
+                )
+                .push(
+                    checkbox(t!("cleanup-strip-quality"), cleanup.strip_quality).on_toggle(|b| {
+                        ViewMessage::CleanupViewMessage(Message::StripQualityToggled(b))
+                    }),
+                )
+                .push(
+                    checkbox(t!("cleanup-strip-symbols"), cleanup.strip_symbols).on_toggle(|b| {
+                        ViewMessage::CleanupViewMessage(Message::StripSymbolsToggled(b))
+                    }),
+                )
+                .push(rules)
+                .push(
+                    button(text(t!("add-rule")))
+                        .on_press(ViewMessage::CleanupViewMessage(Message::AddRule))
+                        .padding(10),
+                )
+                .push(text(t!("cleanup-preview", count = self.preview.len())).size(16))
+                .push(scrollable(preview).height(Length::Fill).width(Length::Fill)),
+        )
+        .padding(20)
+        .center_x(Length::Fill)
+        .into()
+    }
+}
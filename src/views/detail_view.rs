@@ -0,0 +1,329 @@
+use super::{Navigation, View, ViewMessage};
+use crate::ffprobe::{probe_in_background, ProbeCache, StreamInfo};
+use crate::hls::{inspect_in_background, MasterPlaylist, VariantChoice};
+use crate::keymap::Action;
+use crate::m3u::Channel;
+use crate::mpv::play_channel;
+use crate::state::UserState;
+use crate::t;
+
+use iced::widget::{button, scrollable, text, text_input, Column, Container, Row};
+use iced::{Alignment, Element, Length};
+
+pub struct DetailView {
+    channel: Channel,
+    state: UserState,
+    master: Option<Result<MasterPlaylist, String>>,
+    cap_input: String,
+    info: Option<StreamInfo>,
+    probing: bool,
+    probe_error: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    BackToChannels,
+    Play,
+    Inspect,
+    Probe,
+    VariantPinned(usize),
+    AutomaticSelected,
+    CapChanged(String),
+    CapSubmitted,
+}
+
+impl DetailView {
+    pub fn new(channel: Channel) -> Self {
+        inspect_in_background(channel.url.clone());
+        let state = UserState::load();
+        let cap_input = match state.variant_choice(&channel) {
+            Some(VariantChoice::Capped { bandwidth }) => (bandwidth / 1000).to_string(),
+            _ => String::new(),
+        };
+        Self {
+            info: ProbeCache::load().get(&channel).cloned(),
+            channel,
+            state,
+            master: None,
+            cap_input,
+            probing: false,
+            probe_error: None,
+        }
+    }
+
+    fn set_choice(&mut self, choice: Option<VariantChoice>) {
+        match UserState::update(|state| state.set_variant_choice(&self.channel, choice)) {
+            Ok(state) => self.state = state,
+            Err(e) => eprintln!("Failed to save the variant choice: {}", e),
+        }
+    }
+
+    fn choice_label(&self) -> String {
+        match self.state.variant_choice(&self.channel) {
+            Some(VariantChoice::Pinned { bandwidth }) => {
+                t!("variant-pinned", rate = bandwidth / 1000)
+            }
+            Some(VariantChoice::Capped { bandwidth }) => {
+                t!("variant-capped", rate = bandwidth / 1000)
+            }
+            None => t!("variant-automatic"),
+        }
+    }
+
+    fn create_variants(&self) -> Element<ViewMessage> {
+        let master = match &self.master {
+            None => return text(t!("inspecting")).into(),
+            Some(Err(e)) => return text(t!("inspection-failed", error = e)).into(),
+            Some(Ok(master)) if master.variants.is_empty() => {
+                return text(t!("single-variant")).into()
+            }
+            Some(Ok(master)) => master,
+        };
+        let pinned = match self.state.variant_choice(&self.channel) {
+            Some(VariantChoice::Pinned { bandwidth }) => Some(bandwidth),
+            _ => None,
+        };
+
+        let variants =
+            master.variants.iter().enumerate().fold(
+                Column::new().spacing(5),
+                |column, (index, variant)| {
+                    let label = if pinned == Some(variant.bandwidth) {
+                        format!("★ {}", variant.label())
+                    } else {
+                        variant.label()
+                    };
+                    column.push(
+                        Row::new()
+                            .spacing(10)
+                            .align_y(Alignment::Center)
+                            .push(text(label).width(Length::Fill))
+                            .push(button(text(t!("pin"))).on_press(
+                                ViewMessage::DetailViewMessage(Message::VariantPinned(index)),
+                            )),
+                    )
+                },
+            );
+
+        let audio = master
+            .audio
+            .iter()
+            .fold(Column::new().spacing(5), |column, audio| {
+                let language = audio.language.as_deref().unwrap_or("?");
+                column.push(text(t!(
+                    "audio-rendition",
+                    group = &audio.group_id,
+                    name = &audio.name,
+                    language = language
+                )))
+            });
+
+        Column::new().spacing(10).push(variants).push(audio).into()
+    }
+
+    fn create_info(&self) -> Element<ViewMessage> {
+        if self.probing {
+            return text(t!("probing")).into();
+        }
+        if let Some(error) = &self.probe_error {
+            return text(t!("probe-failed", error = error)).into();
+        }
+        let Some(info) = &self.info else {
+            return text(t!("no-probe")).into();
+        };
+
+        let mut lines = vec![t!("probe-container", container = &info.container)];
+        for video in &info.video {
+            let frame_rate = video
+                .frame_rate
+                .map(|rate| {
+                    format!(
+                        " · {}",
+                        t!("probe-frame-rate", rate = format!("{:.2}", rate))
+                    )
+                })
+                .unwrap_or_default();
+            lines.push(t!(
+                "probe-video",
+                codec = &video.codec,
+                width = video.width,
+                height = video.height,
+                frame_rate = frame_rate
+            ));
+        }
+        for audio in &info.audio {
+            let channels = audio
+                .channels
+                .map(|c| format!(" · {}", t!("probe-audio-channels", count = c)))
+                .unwrap_or_default();
+            let language = audio
+                .language
+                .clone()
+                .unwrap_or_else(|| t!("unknown-language"));
+            lines.push(t!(
+                "probe-audio",
+                codec = &audio.codec,
+                language = language,
+                channels = channels
+            ));
+        }
+        for subtitle in &info.subtitles {
+            let language = subtitle
+                .language
+                .clone()
+                .unwrap_or_else(|| t!("unknown-language"));
+            lines.push(t!(
+                "probe-subtitles",
+                codec = &subtitle.codec,
+                language = language
+            ));
+        }
+
+        lines
+            .into_iter()
+            .fold(Column::new().spacing(5), |column, line| {
+                column.push(text(line))
+            })
+            .into()
+    }
+}
+
+impl View for DetailView {
+    fn update(&mut self, message: ViewMessage) -> Option<Navigation> {
+        match message {
+            ViewMessage::DetailViewMessage(msg) => match msg {
+                Message::BackToChannels => {
+                    return Some(Navigation::Back);
+                }
+                Message::Play => {
+                    play_channel(self.channel.clone());
+                }
+                Message::Inspect => {
+                    self.master = None;
+                    inspect_in_background(self.channel.url.clone());
+                }
+                Message::Probe => {
+                    self.probing = true;
+                    self.probe_error = None;
+                    probe_in_background(self.channel.clone());
+                }
+                Message::VariantPinned(index) => {
+                    if let Some(Ok(master)) = &self.master {
+                        let bandwidth = master.variants[index].bandwidth;
+                        self.set_choice(Some(VariantChoice::Pinned { bandwidth }));
+                    }
+                }
+                Message::AutomaticSelected => {
+                    self.cap_input.clear();
+                    self.set_choice(None);
+                }
+                Message::CapChanged(input) => {
+                    if input.chars().all(|c| c.is_ascii_digit()) {
+                        self.cap_input = input;
+                    }
+                }
+                Message::CapSubmitted => {
+                    let choice = self
+                        .cap_input
+                        .parse::<u64>()
+                        .ok()
+                        .filter(|kbps| *kbps > 0)
+                        .map(|kbps| VariantChoice::Capped {
+                            bandwidth: kbps * 1000,
+                        });
+                    self.set_choice(choice);
+                }
+            },
+            ViewMessage::HlsInspected(url, result) if url == self.channel.url => {
+                self.master = Some(result);
+            }
+            ViewMessage::StreamProbed(key, result) if key == self.channel.key() => {
+                self.probing = false;
+                match result {
+                    Ok(info) => self.info = Some(info),
+                    Err(error) => self.probe_error = Some(error),
+                }
+            }
+            ViewMessage::Navigate(Action::Back) => {
+                return self.update(ViewMessage::DetailViewMessage(Message::BackToChannels));
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn view(&self) -> Element<ViewMessage> {
+        let actions = Row::new()
+            .spacing(10)
+            .push(
+                button(text(t!("back")))
+                    .on_press(ViewMessage::DetailViewMessage(Message::BackToChannels))
+                    .padding(10),
+            )
+            .push(
+                button(text(t!("play")))
+                    .on_press(ViewMessage::DetailViewMessage(Message::Play))
+                    .padding(10),
+            )
+            .push(
+                button(text(t!("inspect-again")))
+                    .on_press(ViewMessage::DetailViewMessage(Message::Inspect))
+                    .padding(10),
+            )
+            .push(
+                button(text(t!("probe")))
+                    .on_press_maybe(
+                        (!self.probing).then_some(ViewMessage::DetailViewMessage(Message::Probe)),
+                    )
+                    .padding(10),
+            );
+
+        let choice = Row::new()
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .push(text(self.choice_label()).width(Length::Fill))
+            .push(
+                text_input(&t!("max-rate"), &self.cap_input)
+                    .on_input(|s| ViewMessage::DetailViewMessage(Message::CapChanged(s)))
+                    .on_submit(ViewMessage::DetailViewMessage(Message::CapSubmitted))
+                    .width(200),
+            )
+            .push(
+                button(text(t!("automatic")))
+                    .on_press(ViewMessage::DetailViewMessage(Message::AutomaticSelected)),
+            );
+
+        let streams = self
+            .channel
+            .streams()
+            .fold(Column::new().spacing(5), |column, url| {
+                column.push(text(url).size(14))
+            });
+
+        Container::new(
+            Column::new()
+                .spacing(20)
+                .push(actions)
+                .push(text(&self.channel.name).size(24))
+                .push(streams)
+                .push(choice)
+                .push(
+                    Row::new()
+                        .spacing(20)
+                        .push(
+                            scrollable(self.create_variants())
+                                .height(Length::Fill)
+                                .width(Length::FillPortion(3)),
+                        )
+                        .push(
+                            scrollable(self.create_info())
+                                .height(Length::Fill)
+                                .width(Length::FillPortion(2)),
+                        ),
+                ),
+        )
+        .padding(20)
+        .center_x(Length::Fill)
+        .into()
+    }
+}
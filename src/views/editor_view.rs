@@ -0,0 +1,362 @@
+use super::{Navigation, View, ViewMessage};
+use crate::config::Config;
+use crate::keymap::Action;
+use crate::m3u::{Channel, Group};
+use crate::state::{ChannelEdit, UserState};
+use crate::store;
+use crate::t;
+
+use iced::widget::{button, checkbox, scrollable, text, text_input, Column, Container, Row};
+use iced::{Alignment, Element, Length};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+pub struct EditorView {
+    groups: Arc<Vec<Group>>,
+    state: UserState,
+    edited_groups: Vec<Group>,
+    selected_group: Option<String>,
+    name_inputs: BTreeMap<String, String>,
+    group_inputs: BTreeMap<String, String>,
+    /// Locks are only offered once a parental PIN is set
+    locks_enabled: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    BackToGroups,
+    GroupSelected(usize),
+    GroupHidden(usize, bool),
+    GroupMoved(usize, bool),
+    GroupLocked(usize, bool),
+    ChannelNameChanged(usize, String),
+    ChannelNameSubmitted(usize),
+    ChannelReset(usize),
+    ChannelGroupChanged(usize, String),
+    ChannelGroupSubmitted(usize),
+    ChannelHidden(usize, bool),
+    ChannelMoved(usize, bool),
+    ChannelLocked(usize, bool),
+}
+
+impl EditorView {
+    pub fn new() -> Self {
+        let groups = store::groups();
+        let state = UserState::load();
+        Self {
+            edited_groups: state.apply(&groups, true, false),
+            groups,
+            state,
+            selected_group: None,
+            name_inputs: BTreeMap::new(),
+            group_inputs: BTreeMap::new(),
+            locks_enabled: Config::load().parental.pin_hash.is_some(),
+        }
+    }
+
+    fn channels(&self) -> &[Channel] {
+        self.edited_groups
+            .iter()
+            .find(|g| Some(&g.name) == self.selected_group.as_ref())
+            .map(|g| g.channels.as_slice())
+            .unwrap_or_default()
+    }
+
+    fn save(&mut self, change: impl FnOnce(&mut UserState)) {
+        match UserState::update(change) {
+            Ok(state) => self.state = state,
+            Err(e) => eprintln!("Failed to save channel edits: {}", e),
+        }
+        self.edited_groups = self.state.apply(&self.groups, true, false);
+    }
+
+    fn update_channel(&mut self, index: usize, change: impl FnOnce(&Channel, &mut ChannelEdit)) {
+        let channel = self.channels()[index].clone();
+        self.save(|state| {
+            let mut edit = state.edit(&channel);
+            change(&channel, &mut edit);
+            state.set_edit(&channel, edit);
+        });
+    }
+
+    fn create_group_row(&self, index: usize, group: &Group) -> Element<ViewMessage> {
+        let hidden = self.state.hidden_groups.contains(&group.name);
+        let locked = self.state.locked_groups.contains(&group.name);
+        let label = if hidden {
+            format!("({})", group.name)
+        } else {
+            group.name.clone()
+        };
+
+        Row::new()
+            .spacing(5)
+            .align_y(Alignment::Center)
+            .push(
+                button(text(label))
+                    .on_press(ViewMessage::EditorViewMessage(Message::GroupSelected(
+                        index,
+                    )))
+                    .width(Length::Fill),
+            )
+            .push(
+                checkbox(t!("group-hidden"), hidden).on_toggle(move |b| {
+                    ViewMessage::EditorViewMessage(Message::GroupHidden(index, b))
+                }),
+            )
+            .push_maybe(self.locks_enabled.then(|| {
+                checkbox(t!("group-locked"), locked).on_toggle(move |b| {
+                    ViewMessage::EditorViewMessage(Message::GroupLocked(index, b))
+                })
+            }))
+            .push(
+                button("▲").on_press(ViewMessage::EditorViewMessage(Message::GroupMoved(
+                    index, true,
+                ))),
+            )
+            .push(
+                button("▼").on_press(ViewMessage::EditorViewMessage(Message::GroupMoved(
+                    index, false,
+                ))),
+            )
+            .into()
+    }
+
+    fn create_channel_row(&self, index: usize, channel: &Channel) -> Element<ViewMessage> {
+        let edit = self.state.edit(channel);
+        let locked = self.state.locked_channels.contains(&channel.key());
+        let name_input = self
+            .name_inputs
+            .get(&channel.key())
+            .unwrap_or(&channel.name);
+        let group_input = self
+            .group_inputs
+            .get(&channel.key())
+            .unwrap_or(&channel.group);
+
+        Row::new()
+            .spacing(5)
+            .align_y(Alignment::Center)
+            .push(
+                text_input(&channel.original_name, name_input)
+                    .on_input(move |s| {
+                        ViewMessage::EditorViewMessage(Message::ChannelNameChanged(index, s))
+                    })
+                    .on_submit(ViewMessage::EditorViewMessage(
+                        Message::ChannelNameSubmitted(index),
+                    ))
+                    .width(Length::FillPortion(3)),
+            )
+            .push(
+                text_input(&t!("group"), group_input)
+                    .on_input(move |s| {
+                        ViewMessage::EditorViewMessage(Message::ChannelGroupChanged(index, s))
+                    })
+                    .on_submit(ViewMessage::EditorViewMessage(
+                        Message::ChannelGroupSubmitted(index),
+                    ))
+                    .width(Length::FillPortion(2)),
+            )
+            .push_maybe((edit.name.is_some() || edit.group.is_some()).then(|| {
+                button(text(t!("reset-channel")))
+                    .on_press(ViewMessage::EditorViewMessage(Message::ChannelReset(index)))
+            }))
+            .push(
+                checkbox(t!("channel-hidden"), edit.hidden).on_toggle(move |b| {
+                    ViewMessage::EditorViewMessage(Message::ChannelHidden(index, b))
+                }),
+            )
+            .push_maybe(self.locks_enabled.then(|| {
+                checkbox(t!("channel-locked"), locked).on_toggle(move |b| {
+                    ViewMessage::EditorViewMessage(Message::ChannelLocked(index, b))
+                })
+            }))
+            .push(
+                button("▲").on_press(ViewMessage::EditorViewMessage(Message::ChannelMoved(
+                    index, true,
+                ))),
+            )
+            .push(
+                button("▼").on_press(ViewMessage::EditorViewMessage(Message::ChannelMoved(
+                    index, false,
+                ))),
+            )
+            .into()
+    }
+}
+
+/// Swaps an element with its neighbour, returns false at the boundaries
+fn move_element<T>(elements: &mut [T], index: usize, up: bool) -> bool {
+    let target = if up {
+        index.checked_sub(1)
+    } else {
+        Some(index + 1).filter(|&i| i < elements.len())
+    };
+    let Some(target) = target else {
+        return false;
+    };
+    elements.swap(index, target);
+    true
+}
+
+impl Default for EditorView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl View for EditorView {
+    fn update(&mut self, message: ViewMessage) -> Option<Navigation> {
+        match message {
+            ViewMessage::EditorViewMessage(msg) => match msg {
+                Message::BackToGroups => {
+                    return Some(Navigation::Back);
+                }
+                Message::GroupSelected(index) => {
+                    self.selected_group = Some(self.edited_groups[index].name.clone());
+                    self.name_inputs.clear();
+                    self.group_inputs.clear();
+                }
+                Message::GroupHidden(index, hidden) => {
+                    let name = self.edited_groups[index].name.clone();
+                    self.save(|state| {
+                        if hidden {
+                            state.hidden_groups.insert(name);
+                        } else {
+                            state.hidden_groups.remove(&name);
+                        }
+                    });
+                }
+                Message::GroupLocked(index, locked) => {
+                    let name = self.edited_groups[index].name.clone();
+                    self.save(|state| {
+                        if locked {
+                            state.locked_groups.insert(name);
+                        } else {
+                            state.locked_groups.remove(&name);
+                        }
+                    });
+                }
+                Message::GroupMoved(index, up) => {
+                    let mut order: Vec<String> =
+                        self.edited_groups.iter().map(|g| g.name.clone()).collect();
+                    if move_element(&mut order, index, up) {
+                        self.save(|state| state.group_order = order);
+                    }
+                }
+                Message::ChannelNameChanged(index, name) => {
+                    let key = self.channels()[index].key();
+                    self.name_inputs.insert(key, name);
+                }
+                Message::ChannelNameSubmitted(index) => {
+                    let key = self.channels()[index].key();
+                    if let Some(name) = self.name_inputs.remove(&key) {
+                        self.update_channel(index, |channel, edit| {
+                            edit.name = (!name.trim().is_empty() && name != channel.original_name)
+                                .then_some(name);
+                        });
+                    }
+                }
+                Message::ChannelReset(index) => {
+                    let key = self.channels()[index].key();
+                    self.name_inputs.remove(&key);
+                    self.group_inputs.remove(&key);
+                    self.update_channel(index, |_, edit| {
+                        edit.name = None;
+                        edit.group = None;
+                    });
+                }
+                Message::ChannelGroupChanged(index, group) => {
+                    let key = self.channels()[index].key();
+                    self.group_inputs.insert(key, group);
+                }
+                Message::ChannelGroupSubmitted(index) => {
+                    let key = self.channels()[index].key();
+                    if let Some(group) = self.group_inputs.remove(&key) {
+                        self.update_channel(index, |_, edit| {
+                            edit.group = (!group.trim().is_empty()).then_some(group);
+                        });
+                    }
+                }
+                Message::ChannelHidden(index, hidden) => {
+                    self.update_channel(index, |_, edit| edit.hidden = hidden);
+                }
+                Message::ChannelLocked(index, locked) => {
+                    let key = self.channels()[index].key();
+                    self.save(|state| {
+                        if locked {
+                            state.locked_channels.insert(key);
+                        } else {
+                            state.locked_channels.remove(&key);
+                        }
+                    });
+                }
+                Message::ChannelMoved(index, up) => {
+                    let mut order: Vec<String> = self.channels().iter().map(|c| c.key()).collect();
+                    if let Some(group) = self.selected_group.clone() {
+                        if move_element(&mut order, index, up) {
+                            self.save(|state| {
+                                state.channel_order.insert(group, order);
+                            });
+                        }
+                    }
+                }
+            },
+            ViewMessage::Navigate(Action::Back) => {
+                return self.update(ViewMessage::EditorViewMessage(Message::BackToGroups));
+            }
+            ViewMessage::PlaylistRefreshed(groups, _) => {
+                self.groups = groups;
+                self.state = UserState::load();
+                self.edited_groups = self.state.apply(&self.groups, true, false);
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn view(&self) -> Element<ViewMessage> {
+        let back_button = button(text(t!("back")))
+            .on_press(ViewMessage::EditorViewMessage(Message::BackToGroups))
+            .padding(10);
+
+        let groups = self
+            .edited_groups
+            .iter()
+            .enumerate()
+            .fold(Column::new().spacing(5), |column, (index, group)| {
+                column.push(self.create_group_row(index, group))
+            });
+
+        let channels: Element<ViewMessage> = match &self.selected_group {
+            Some(_) => self
+                .channels()
+                .iter()
+                .enumerate()
+                .fold(Column::new().spacing(5), |column, (index, channel)| {
+                    column.push(self.create_channel_row(index, channel))
+                })
+                .into(),
+            None => text(t!("select-group")).size(16).into(),
+        };
+
+        Container::new(
+            Column::new().spacing(20).push(back_button).push(
+                Row::new()
+                    .spacing(20)
+                    .push(
+                        scrollable(groups)
+                            .height(Length::Fill)
+                            .width(Length::FillPortion(1)),
+                    )
+                    .push(
+                        scrollable(channels)
+                            .height(Length::Fill)
+                            .width(Length::FillPortion(2)),
+                    ),
+            ),
+        )
+        .padding(20)
+        .center_x(Length::Fill)
+        .into()
+    }
+}
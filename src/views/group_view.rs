@@ -1,85 +1,221 @@
-use super::iced_utils::create_buttons;
-use super::{ChannelView, SettingsView, View, ViewMessage};
+use super::iced_utils::{create_buttons, move_focus, GRID_SCROLLABLE, SEARCH_INPUT};
+use super::{
+    ChannelView, EditorView, Navigation, PinView, ProfileView, SettingsView, TvGroupView, View,
+    ViewMessage,
+};
+use crate::config::Config;
+use crate::health::check_in_background;
+use crate::i18n::group_name;
+use crate::keymap::Action;
 use crate::m3u::Group;
-use iced::widget::{button, scrollable, text_input, Column, Container};
+use crate::parental::{self, ParentalConfig};
+use crate::state::UserState;
+use crate::store;
+use crate::t;
+use iced::widget::{button, scrollable, text, text_input, Column, Container, Row};
 use iced::{Element, Length};
 
 use std::cmp::Ordering;
+use std::sync::Arc;
 
 pub struct GroupView {
-    m3u_path: Option<String>,
-    groups: Vec<Group>,
-    filtered_groups: Vec<Group>,
+    groups: Arc<Vec<Group>>,
+    edited_groups: Vec<Group>,
+    favorites: Option<Group>,
+    recent: Option<Group>,
+    /// Indices of the shown groups among the favorites, the recent channels
+    /// and the edited groups
+    filtered_groups: Vec<usize>,
     search_text: String,
+    focused: Option<usize>,
+    parental: ParentalConfig,
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
     GroupSelected(usize),
     SettingsSelected,
+    EditorSelected,
+    CheckSelected,
+    TvModeSelected,
+    ParentalSelected,
+    ProfilesSelected,
     SearchTextChanged(String),
 }
 
 impl GroupView {
-    pub fn new(groups: Vec<Group>, m3u_path: Option<String>) -> Self {
-        Self {
-            m3u_path,
-            groups: groups.clone(),
-            filtered_groups: groups,
+    pub fn new() -> Self {
+        let mut view = Self {
+            groups: store::groups(),
+            edited_groups: Vec::new(),
+            favorites: None,
+            recent: None,
+            filtered_groups: Vec::new(),
             search_text: String::new(),
-        }
+            focused: None,
+            parental: ParentalConfig::default(),
+        };
+        view.apply_edits();
+        view
+    }
+
+    fn apply_edits(&mut self) {
+        self.parental = Config::load().parental;
+        let state = UserState::load();
+        self.edited_groups = state.apply(&self.groups, false, false);
+        self.favorites = state.favorites_group(&self.edited_groups);
+        self.recent = state.recent_group(&self.edited_groups);
+        update_filtered_groups(self);
+    }
+
+    fn groups(&self) -> impl Iterator<Item = &Group> {
+        self.favorites
+            .iter()
+            .chain(self.recent.iter())
+            .chain(self.edited_groups.iter())
+    }
+}
+
+impl Default for GroupView {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl View for GroupView {
-    fn update(&mut self, message: ViewMessage) -> Option<Box<dyn View>> {
+    fn update(&mut self, message: ViewMessage) -> Option<Navigation> {
         match message {
             ViewMessage::GroupViewMessage(msg) => match msg {
                 Message::GroupSelected(index) => {
-                    let selected_group = self.filtered_groups[index].clone();
-                    return Some(Box::new(ChannelView::new(
-                        selected_group,
-                        self.groups.clone(),
-                        self.m3u_path.clone(),
-                    )));
+                    let selected_group = self.groups().nth(self.filtered_groups[index])?.clone();
+                    return Some(Navigation::Push(Box::new(ChannelView::new(selected_group))));
                 }
                 Message::SettingsSelected => {
-                    return Some(Box::new(SettingsView::new(
-                        self.groups.clone(),
-                        self.m3u_path.clone(),
-                    )));
+                    return Some(Navigation::Push(Box::new(SettingsView::new())));
+                }
+                Message::EditorSelected => {
+                    return Some(Navigation::Push(Box::new(EditorView::new())));
+                }
+                Message::CheckSelected => {
+                    let channels = self
+                        .edited_groups
+                        .iter()
+                        .flat_map(|g| g.channels.iter().cloned())
+                        .collect();
+                    check_in_background(channels);
+                }
+                Message::TvModeSelected => {
+                    let mut config = Config::load();
+                    config.tv_mode = true;
+                    if let Err(e) = config.save() {
+                        eprintln!("Failed to save settings: {}", e);
+                    }
+                    return Some(Navigation::Reset(Box::new(TvGroupView::new())));
+                }
+                Message::ParentalSelected => {
+                    if self.parental.is_restricted() {
+                        return Some(Navigation::Push(Box::new(PinView::new())));
+                    }
+                    parental::lock();
+                    self.apply_edits();
+                }
+                Message::ProfilesSelected => {
+                    return Some(Navigation::Push(Box::new(ProfileView::new())));
                 }
                 Message::SearchTextChanged(new_text) => {
                     self.search_text = new_text;
+                    self.focused = None;
                     update_filtered_groups(self);
                 }
             },
+            ViewMessage::Navigate(Action::Activate) => {
+                let index = self.focused.unwrap_or_default();
+                if index < self.filtered_groups.len() {
+                    return self
+                        .update(ViewMessage::GroupViewMessage(Message::GroupSelected(index)));
+                }
+            }
+            ViewMessage::Navigate(Action::Back) => {}
+            ViewMessage::Navigate(action) => {
+                self.focused = move_focus(self.focused, self.filtered_groups.len(), action);
+            }
+            ViewMessage::PlaylistRefreshed(groups, _) => {
+                self.groups = groups;
+                self.focused = None;
+                self.apply_edits();
+            }
             _ => {}
         }
         None
     }
 
     fn view(&self) -> Element<ViewMessage> {
-        let settings_button = button("Paramètres")
+        let settings_button = button(text(t!("settings")))
             .on_press(ViewMessage::GroupViewMessage(Message::SettingsSelected))
             .padding(10);
 
-        let search_bar = text_input("Rechercher", &self.search_text)
+        let editor_button = button(text(t!("edit-channels")))
+            .on_press(ViewMessage::GroupViewMessage(Message::EditorSelected))
+            .padding(10);
+
+        let check_button = button(text(t!("check-streams")))
+            .on_press(ViewMessage::GroupViewMessage(Message::CheckSelected))
+            .padding(10);
+
+        let tv_button = button(text(t!("tv-mode")))
+            .on_press(ViewMessage::GroupViewMessage(Message::TvModeSelected))
+            .padding(10);
+
+        let profiles_button = button(text(t!("profiles")))
+            .on_press(ViewMessage::GroupViewMessage(Message::ProfilesSelected))
+            .padding(10);
+
+        let parental_button = self.parental.pin_hash.is_some().then(|| {
+            let label = if self.parental.is_restricted() {
+                t!("unlock")
+            } else {
+                t!("lock")
+            };
+            button(text(label))
+                .on_press(ViewMessage::GroupViewMessage(Message::ParentalSelected))
+                .padding(10)
+        });
+
+        let search_bar = text_input(&t!("search"), &self.search_text)
+            .id(SEARCH_INPUT.clone())
             .padding(10)
             .size(20)
             .on_input(|s| ViewMessage::GroupViewMessage(Message::SearchTextChanged(s)));
 
-        let groups = create_buttons(self.filtered_groups.clone(), |index| {
-            ViewMessage::GroupViewMessage(Message::GroupSelected(index))
-        });
+        let groups: Vec<&Group> = self.groups().collect();
+        let names: Vec<String> = self
+            .filtered_groups
+            .iter()
+            .map(|&index| groups[index].name.clone())
+            .collect();
+        let groups = create_buttons(
+            names,
+            |index| ViewMessage::GroupViewMessage(Message::GroupSelected(index)),
+            None,
+            self.focused,
+        );
 
         Container::new(
             Column::new()
                 .spacing(20)
                 .push(
-                    Container::new(settings_button)
-                        .padding(10)
-                        .center_x(Length::Fill),
+                    Container::new(
+                        Row::new()
+                            .spacing(10)
+                            .push(settings_button)
+                            .push(editor_button)
+                            .push(check_button)
+                            .push(tv_button)
+                            .push(profiles_button)
+                            .push_maybe(parental_button),
+                    )
+                    .padding(10)
+                    .center_x(Length::Fill),
                 )
                 .push(
                     Container::new(search_bar)
@@ -87,34 +223,50 @@ impl View for GroupView {
                         .center_x(Length::Fill),
                 )
                 .push(
-                    Container::new(scrollable(groups).height(Length::Fill).width(Length::Fill))
-                        .padding(10),
+                    Container::new(
+                        scrollable(groups)
+                            .id(GRID_SCROLLABLE.clone())
+                            .on_scroll(|viewport| {
+                                ViewMessage::GridScrolled(viewport.absolute_offset())
+                            })
+                            .height(Length::Fill)
+                            .width(Length::Fill),
+                    )
+                    .padding(10),
                 ),
         )
         .padding(20)
         .center_x(Length::Fill)
         .into()
     }
+
+    fn resume(&mut self) {
+        self.groups = store::groups();
+        self.apply_edits();
+    }
+
+    fn grid_focus(&self) -> Option<(usize, usize)> {
+        Some((self.focused?, self.filtered_groups.len()))
+    }
 }
 
 fn update_filtered_groups(state: &mut GroupView) {
+    let groups = state.groups().enumerate();
+
     if state.search_text.is_empty() {
-        state.filtered_groups = state.groups.clone();
+        state.filtered_groups = groups.map(|(index, _)| index).collect();
     } else {
         let search_lower = state.search_text.to_lowercase().replace(' ', "");
-        let mut filtered: Vec<_> = state
-            .groups
-            .iter()
-            .filter(|group| {
-                let group_name_lower = group.name.to_lowercase().replace(' ', "");
+        let mut filtered: Vec<_> = groups
+            .filter(|(_, group)| {
+                let group_name_lower = group_name(&group.name).to_lowercase().replace(' ', "");
                 search_lower.chars().all(|c| group_name_lower.contains(c))
             })
-            .cloned()
             .collect();
 
-        filtered.sort_by(|a, b| {
-            let group_a_name = a.name.to_lowercase().replace(' ', "");
-            let group_b_name = b.name.to_lowercase().replace(' ', "");
+        filtered.sort_by(|(_, a), (_, b)| {
+            let group_a_name = group_name(&a.name).to_lowercase().replace(' ', "");
+            let group_b_name = group_name(&b.name).to_lowercase().replace(' ', "");
 
             let score_a = calculate_match_score(&search_lower, &group_a_name);
             let score_b = calculate_match_score(&search_lower, &group_b_name);
@@ -122,7 +274,7 @@ fn update_filtered_groups(state: &mut GroupView) {
             score_b.partial_cmp(&score_a).unwrap_or(Ordering::Equal)
         });
 
-        state.filtered_groups = filtered;
+        state.filtered_groups = filtered.into_iter().map(|(index, _)| index).collect();
     }
 }
 
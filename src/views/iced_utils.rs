@@ -1,30 +1,175 @@
-use crate::m3u::Named;
+use crate::m3u::{write_m3u, Channel, Named};
 
 use super::ViewMessage;
-use iced::widget::{button, Column, Row};
-use iced::Length;
+use crate::i18n::group_name;
+use crate::keymap::Action;
+use crate::t;
+use iced::alignment::Horizontal;
+use iced::widget::image::Handle;
+use iced::widget::{button, image, mouse_area, scrollable, text, text_input, Column, Row, Space};
+use iced::{Alignment, Border, Length, Theme};
+use rfd::FileDialog;
+use std::fmt;
+use std::sync::LazyLock;
+
+/// Choice of a pick list where `None` lets the application decide
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrAutomatic<T>(pub Option<T>);
+
+impl<T: fmt::Display> fmt::Display for OrAutomatic<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.0 {
+            Some(value) => value.fmt(f),
+            None => write!(f, "{}", t!("automatic")),
+        }
+    }
+}
+
+/// Buttons per row of the grids
+pub const GRID_COLUMNS: usize = 4;
+/// Margin of the TV mode, televisions may crop the edges of the picture
+pub const OVERSCAN_PADDING: u16 = 60;
+pub static GRID_SCROLLABLE: LazyLock<scrollable::Id> =
+    LazyLock::new(|| scrollable::Id::new("grid"));
+pub static SEARCH_INPUT: LazyLock<text_input::Id> = LazyLock::new(|| text_input::Id::new("search"));
 
 pub fn create_buttons<T: Named + 'static>(
     elements: Vec<T>,
     on_press: fn(usize) -> ViewMessage,
+    on_right_press: Option<fn(usize) -> ViewMessage>,
+    focused: Option<usize>,
 ) -> Column<'static, ViewMessage> {
     elements
         .iter()
         .enumerate()
         .collect::<Vec<_>>()
-        .chunks(4)
+        .chunks(GRID_COLUMNS)
         .fold(Column::new().spacing(10), |column, chunk| {
             let row = chunk
                 .iter()
                 .fold(Row::new().spacing(10), |row, (index, element)| {
+                    let is_focused = focused == Some(*index);
+                    let button = button(iced::widget::Text::new(group_name(element.name())))
+                        .on_press(on_press(*index))
+                        .padding(10)
+                        .width(Length::Fill)
+                        .height(50)
+                        .style(move |theme, status| focus_style(theme, status, is_focused, 3.0));
+                    match on_right_press {
+                        Some(on_right_press) => {
+                            row.push(mouse_area(button).on_right_press(on_right_press(*index)))
+                        }
+                        None => row.push(button),
+                    }
+                });
+            column.push(row)
+        })
+}
+
+/// Primary button outlined when it has the keyboard focus
+fn focus_style(theme: &Theme, status: button::Status, focused: bool, width: f32) -> button::Style {
+    let style = button::primary(theme, status);
+    if !focused {
+        return style;
+    }
+    button::Style {
+        border: Border {
+            color: theme.palette().text,
+            width,
+            ..style.border
+        },
+        ..style
+    }
+}
+
+/// Large tile of the TV mode, with the logo above the label
+pub struct Tile {
+    pub label: String,
+    pub logo: Option<Handle>,
+}
+
+impl Tile {
+    pub fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            logo: None,
+        }
+    }
+}
+
+pub fn create_tiles(
+    tiles: Vec<Tile>,
+    on_press: fn(usize) -> ViewMessage,
+    focused: Option<usize>,
+) -> Column<'static, ViewMessage> {
+    tiles
+        .into_iter()
+        .enumerate()
+        .collect::<Vec<_>>()
+        .chunks(GRID_COLUMNS)
+        .fold(Column::new().spacing(20), |column, chunk| {
+            let row = chunk
+                .iter()
+                .fold(Row::new().spacing(20), |row, (index, tile)| {
+                    let is_focused = focused == Some(*index);
+                    let logo = tile.logo.clone().map(|handle| image(handle).height(80));
+                    let content = Column::new()
+                        .spacing(10)
+                        .align_x(Alignment::Center)
+                        .push_maybe(logo)
+                        .push(
+                            text(tile.label.clone())
+                                .size(24)
+                                .align_x(Horizontal::Center),
+                        );
                     row.push(
-                        button(iced::widget::Text::new(element.name().to_string()))
+                        button(iced::widget::container(content).center(Length::Fill))
                             .on_press(on_press(*index))
-                            .padding(10)
+                            .padding(15)
                             .width(Length::Fill)
-                            .height(50),
+                            .height(160)
+                            .style(move |theme, status| {
+                                focus_style(theme, status, is_focused, 6.0)
+                            }),
                     )
                 });
+            // Keeps the tiles of the last row as wide as the others
+            let row = (chunk.len()..GRID_COLUMNS)
+                .fold(row, |row, _| row.push(Space::with_width(Length::Fill)));
             column.push(row)
         })
 }
+
+/// Focus moved by an arrow key in a grid of `len` buttons, the first one is
+/// focused when none was
+pub fn move_focus(focused: Option<usize>, len: usize, action: Action) -> Option<usize> {
+    if len == 0 {
+        return None;
+    }
+    let Some(index) = focused.filter(|index| *index < len) else {
+        return Some(0);
+    };
+    let index = match action {
+        Action::Up => index.checked_sub(GRID_COLUMNS).unwrap_or(index),
+        Action::Down => (index + GRID_COLUMNS).min(len - 1),
+        Action::Left => index.saturating_sub(1),
+        Action::Right => (index + 1).min(len - 1),
+        _ => index,
+    };
+    Some(index)
+}
+
+pub fn export_channels(channels: &[Channel], filename: &str) {
+    let filepath = FileDialog::new()
+        .set_title(t!("export-playlist"))
+        .add_filter("M3U", &["m3u", "m3u8"])
+        .set_file_name(format!("{}.m3u", filename))
+        .save_file();
+
+    if let Some(path) = filepath {
+        let path = path.to_string_lossy().into_owned();
+        if let Err(e) = write_m3u(&path, channels) {
+            eprintln!("Failed to export {}: {}", path, e);
+        }
+    }
+}
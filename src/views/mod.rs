@@ -1,23 +1,104 @@
 pub mod channel_view;
+pub mod cleanup_view;
+pub mod detail_view;
+pub mod editor_view;
 pub mod group_view;
 pub mod iced_utils;
+pub mod pin_view;
+pub mod profile_view;
 pub mod settings_view;
+pub mod tv_channel_view;
+pub mod tv_group_view;
+pub mod tv_settings_view;
 
+use crate::config::Config;
+use crate::ffprobe::StreamInfo;
+use crate::health::HealthReport;
+use crate::hls::MasterPlaylist;
+use crate::keymap::Action;
+use crate::m3u::Group;
+use crate::mpv::PlaybackEvent;
+use crate::playlist::PlaylistDiff;
+use iced::keyboard::Key;
+use iced::widget::scrollable::AbsoluteOffset;
 use iced::Element;
+use std::sync::Arc;
 
 pub use channel_view::ChannelView;
+pub use cleanup_view::CleanupView;
+pub use detail_view::DetailView;
+pub use editor_view::EditorView;
 pub use group_view::GroupView;
+pub use pin_view::PinView;
+pub use profile_view::ProfileView;
 pub use settings_view::SettingsView;
+pub use tv_channel_view::TvChannelView;
+pub use tv_group_view::TvGroupView;
+pub use tv_settings_view::TvSettingsView;
 
 pub trait View {
-    fn update(&mut self, message: ViewMessage) -> Option<Box<dyn View>>;
+    fn update(&mut self, message: ViewMessage) -> Option<Navigation>;
     fn view(&self) -> Element<ViewMessage>;
+
+    /// Called when the view is shown again after Back, the playlist or the
+    /// user edits may have changed in between
+    fn resume(&mut self) {}
+
+    /// Focused button of the grid and its number of buttons, to keep it
+    /// scrolled into view
+    fn grid_focus(&self) -> Option<(usize, usize)> {
+        None
+    }
+}
+
+/// Move in the navigation stack of the application
+pub enum Navigation {
+    /// Shows a view, Back returning to the current one as it was left
+    Push(Box<dyn View>),
+    Back,
+    /// Shows a view and forgets the previous ones
+    Reset(Box<dyn View>),
+}
+
+/// Groups view of the interface mode chosen in the settings
+pub fn home() -> Box<dyn View> {
+    if Config::load().tv_mode {
+        Box::new(TvGroupView::new())
+    } else {
+        Box::new(GroupView::new())
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum ViewMessage {
     // Common messages
+    RefreshTick,
+    PlaylistRefreshed(Arc<Vec<Group>>, PlaylistDiff),
+    DismissNotice,
+    KeyPressed(Key),
+    Navigate(Action),
+    ActionTriggered(Action),
+    NumberTick,
+    SystemThemeTick,
+    GridScrolled(AbsoluteOffset),
+    LogoLoaded,
+    FrameDecoded,
+    /// The embedded decoder stopped before the playback, with its last error
+    DecoderStopped(Option<String>),
+    Playback(PlaybackEvent),
+    HealthChecked(String, HealthReport),
+    HealthCheckFinished(usize, usize),
+    HlsInspected(String, Result<MasterPlaylist, String>),
+    StreamProbed(String, Result<StreamInfo, String>),
     GroupViewMessage(group_view::Message),
     ChannelViewMessage(channel_view::Message),
     SettingsViewMessage(settings_view::Message),
+    EditorViewMessage(editor_view::Message),
+    CleanupViewMessage(cleanup_view::Message),
+    DetailViewMessage(detail_view::Message),
+    PinViewMessage(pin_view::Message),
+    ProfileViewMessage(profile_view::Message),
+    TvGroupViewMessage(tv_group_view::Message),
+    TvChannelViewMessage(tv_channel_view::Message),
+    TvSettingsViewMessage(tv_settings_view::Message),
 }
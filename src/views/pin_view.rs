@@ -0,0 +1,93 @@
+use super::{Navigation, View, ViewMessage};
+use crate::keymap::Action;
+use crate::parental;
+use crate::t;
+
+use iced::widget::{button, text, text_input, Column, Container};
+use iced::{Element, Length};
+
+/// Asks for the parental PIN, going back once the locked channels are shown
+pub struct PinView {
+    pin: String,
+    wrong_pin: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    PinChanged(String),
+    PinSubmitted,
+    BackToGroups,
+}
+
+impl PinView {
+    pub fn new() -> Self {
+        Self {
+            pin: String::new(),
+            wrong_pin: false,
+        }
+    }
+}
+
+impl Default for PinView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl View for PinView {
+    fn update(&mut self, message: ViewMessage) -> Option<Navigation> {
+        match message {
+            ViewMessage::PinViewMessage(msg) => match msg {
+                Message::PinChanged(pin) => {
+                    self.pin = pin;
+                }
+                Message::PinSubmitted => {
+                    if parental::unlock(&std::mem::take(&mut self.pin)) {
+                        return Some(Navigation::Back);
+                    }
+                    self.wrong_pin = true;
+                }
+                Message::BackToGroups => {
+                    return Some(Navigation::Back);
+                }
+            },
+            ViewMessage::Navigate(Action::Back) => {
+                return self.update(ViewMessage::PinViewMessage(Message::BackToGroups));
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn view(&self) -> Element<ViewMessage> {
+        let error = if self.wrong_pin {
+            t!("wrong-pin")
+        } else {
+            String::new()
+        };
+
+        Container::new(
+            Column::new()
+                .spacing(20)
+                .width(300)
+                .push(text(t!("enter-pin")).size(20))
+                .push(
+                    text_input(&t!("pin"), &self.pin)
+                        .secure(true)
+                        .padding(10)
+                        .on_input(|s| ViewMessage::PinViewMessage(Message::PinChanged(s)))
+                        .on_submit(ViewMessage::PinViewMessage(Message::PinSubmitted)),
+                )
+                .push(text(error).size(16))
+                .push(
+                    button(text(t!("back")))
+                        .on_press(ViewMessage::PinViewMessage(Message::BackToGroups))
+                        .padding(10),
+                ),
+        )
+        .padding(20)
+        .center_x(Length::Fill)
+        .center_y(Length::Fill)
+        .into()
+    }
+}
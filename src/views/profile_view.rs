@@ -0,0 +1,187 @@
+use super::iced_utils::{create_buttons, move_focus, GRID_SCROLLABLE};
+use super::{home, Navigation, PinView, View, ViewMessage};
+use crate::config::Config;
+use crate::keymap::Action;
+use crate::profile;
+use crate::t;
+
+use iced::widget::{button, scrollable, text, text_input, Column, Container, Row};
+use iced::{Element, Length};
+
+/// Profiles to pick from, the main one first
+pub struct ProfileView {
+    profiles: Vec<String>,
+    new_profile: String,
+    focused: Option<usize>,
+    /// Change of the profiles waiting for the parental PIN
+    pending: Option<Message>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    ProfileSelected(usize),
+    ProfileRemoved(usize),
+    NewProfileChanged(String),
+    AddProfile,
+    BackToGroups,
+}
+
+impl ProfileView {
+    pub fn new() -> Self {
+        Self {
+            profiles: Config::load().profiles,
+            new_profile: String::new(),
+            focused: None,
+            pending: None,
+        }
+    }
+
+    /// Asks for the parental PIN before the message is handled, so that
+    /// profiles can not be used to escape the locks
+    fn ask_pin(&mut self, message: Message) -> Option<Navigation> {
+        if !Config::load().parental.is_restricted() {
+            return None;
+        }
+        self.pending = Some(message);
+        Some(Navigation::Push(Box::new(PinView::new())))
+    }
+
+    /// Profile name of a button, `None` for the main profile
+    fn profile(&self, index: usize) -> Option<String> {
+        index
+            .checked_sub(1)
+            .and_then(|i| self.profiles.get(i).cloned())
+    }
+
+    fn labels(&self) -> Vec<String> {
+        let current = profile::current();
+        std::iter::once(t!("main-profile"))
+            .chain(self.profiles.iter().cloned())
+            .enumerate()
+            .map(|(index, label)| {
+                if self.profile(index) == current {
+                    format!("✔ {}", label)
+                } else {
+                    label
+                }
+            })
+            .collect()
+    }
+}
+
+impl Default for ProfileView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl View for ProfileView {
+    fn update(&mut self, message: ViewMessage) -> Option<Navigation> {
+        match message {
+            ViewMessage::ProfileViewMessage(msg) => match msg {
+                Message::ProfileSelected(index) => {
+                    profile::select(self.profile(index));
+                    return Some(Navigation::Reset(home()));
+                }
+                Message::ProfileRemoved(index) => {
+                    if let Some(navigation) = self.ask_pin(Message::ProfileRemoved(index)) {
+                        return Some(navigation);
+                    }
+                    if let Some(name) = self.profile(index) {
+                        if let Err(e) = profile::remove(&name) {
+                            eprintln!("Failed to remove the profile {}: {}", name, e);
+                        }
+                        self.profiles = Config::load().profiles;
+                        self.focused = None;
+                    }
+                }
+                Message::NewProfileChanged(name) => {
+                    self.new_profile = name;
+                }
+                Message::AddProfile => {
+                    if let Some(navigation) = self.ask_pin(Message::AddProfile) {
+                        return Some(navigation);
+                    }
+                    let name = std::mem::take(&mut self.new_profile);
+                    if let Err(e) = profile::add(name.trim()) {
+                        eprintln!("Failed to add the profile {}: {}", name, e);
+                    }
+                    self.profiles = Config::load().profiles;
+                }
+                Message::BackToGroups => {
+                    return Some(Navigation::Back);
+                }
+            },
+            ViewMessage::Navigate(Action::Activate) => {
+                let index = self.focused.unwrap_or_default();
+                return self.update(ViewMessage::ProfileViewMessage(Message::ProfileSelected(
+                    index,
+                )));
+            }
+            ViewMessage::Navigate(Action::Back) => {
+                return self.update(ViewMessage::ProfileViewMessage(Message::BackToGroups));
+            }
+            ViewMessage::Navigate(action) => {
+                self.focused = move_focus(self.focused, self.profiles.len() + 1, action);
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn view(&self) -> Element<ViewMessage> {
+        let profiles = create_buttons(
+            self.labels(),
+            |index| ViewMessage::ProfileViewMessage(Message::ProfileSelected(index)),
+            Some(|index| ViewMessage::ProfileViewMessage(Message::ProfileRemoved(index))),
+            self.focused,
+        );
+
+        let add_row = Row::new()
+            .spacing(10)
+            .push(
+                text_input(&t!("new-profile"), &self.new_profile)
+                    .on_input(|s| ViewMessage::ProfileViewMessage(Message::NewProfileChanged(s)))
+                    .on_submit(ViewMessage::ProfileViewMessage(Message::AddProfile)),
+            )
+            .push(
+                button(text(t!("add-profile")))
+                    .on_press(ViewMessage::ProfileViewMessage(Message::AddProfile)),
+            );
+
+        Container::new(
+            Column::new()
+                .spacing(20)
+                .push(text(t!("choose-profile")).size(24))
+                .push(
+                    scrollable(profiles)
+                        .id(GRID_SCROLLABLE.clone())
+                        .height(Length::Fill)
+                        .width(Length::Fill),
+                )
+                .push(text(t!("remove-profile-hint")).size(14))
+                .push(add_row)
+                .push(
+                    button(text(t!("back")))
+                        .on_press(ViewMessage::ProfileViewMessage(Message::BackToGroups))
+                        .padding(10),
+                ),
+        )
+        .padding(20)
+        .center_x(Length::Fill)
+        .into()
+    }
+
+    /// Carries out the change the PIN was asked for, once it was entered
+    fn resume(&mut self) {
+        if let Some(message) = self.pending.take() {
+            if !Config::load().parental.is_restricted() {
+                self.update(ViewMessage::ProfileViewMessage(message));
+            }
+        }
+    }
+
+    fn grid_focus(&self) -> Option<(usize, usize)> {
+        Some((self.focused?, self.profiles.len() + 1))
+    }
+}
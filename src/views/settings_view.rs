@@ -1,96 +1,824 @@
-use super::{GroupView, View, ViewMessage};
-use crate::m3u::{parse_m3u, Group};
+use super::iced_utils::{export_channels, OrAutomatic};
+use super::{CleanupView, Navigation, View, ViewMessage};
+use crate::config::Config;
+use crate::duplicates::QUALITIES;
+use crate::i18n::{self, group_name, Language};
+use crate::keymap::Action;
+use crate::m3u::Group;
+use crate::mpv::PlayerPreferences;
+use crate::playlist::{reload_in_background, PlaylistSource, SourceKind};
+use crate::server;
+use crate::state::{UserState, FAVORITES_GROUP};
+use crate::store;
+use crate::t;
+use crate::theme;
 
 use iced::alignment::Horizontal;
-use iced::widget::{button, text, Column, Container, Space};
-use iced::{Element, Length};
+use iced::widget::{
+    button, checkbox, pick_list, scrollable, text, text_input, Column, Container, Row, Space,
+};
+use iced::{Alignment, Element, Length, Theme};
 
 use rfd::FileDialog;
+use std::sync::Arc;
 
 #[derive(Debug, Clone)]
 pub enum Message {
     BackToGroups,
     SelectFile,
+    ReloadSources,
+    ExportFavorites,
+    CleanupSelected,
+    PrefixGroupsToggled(bool),
+    MergeDuplicatesToggled(bool),
+    EmbeddedPlayerToggled(bool),
+    PreferredQualitySelected(OrAutomatic<&'static str>),
+    LanguageSelected(OrAutomatic<Language>),
+    ThemeSelected(OrAutomatic<Theme>),
+    FailoverToggled(bool),
+    AttemptsChanged(String),
+    TimeoutChanged(String),
+    VolumeChanged(String),
+    AudioLanguagesChanged(String),
+    SubtitleLanguagesChanged(String),
+    ServerToggled(bool),
+    ServerAddressChanged(String),
+    ServerPortChanged(String),
+    RestartServer,
+    HdHomeRunToggled(bool),
+    TunerCountChanged(String),
+    TunerGroupsChanged(String),
+    TunerFavoritesToggled(bool),
+    ApiToggled(bool),
+    ApiTokenChanged(String),
+    PinChanged(String),
+    PinSubmitted,
+    LockKeywordsChanged(String),
+    SourceNameChanged(usize, String),
+    SourceToggled(usize, bool),
+    SourceRefreshChanged(usize, String),
+    SourceEpgChanged(usize, String),
+    SourceStartupToggled(usize, bool),
+    RemoveSource(usize),
+    NewUrlChanged(String),
+    AddUrl,
+    XtreamServerChanged(String),
+    XtreamUsernameChanged(String),
+    XtreamPasswordChanged(String),
+    AddXtream,
 }
 
 pub struct SettingsView {
-    m3u_path: Option<String>,
-    groups: Vec<Group>,
+    config: Config,
+    /// Player settings of the current profile
+    player: PlayerPreferences,
+    groups: Arc<Vec<Group>>,
+    last_diff: Option<String>,
+    server_error: Option<String>,
+    tuner_groups: String,
+    new_pin: String,
+    lock_keywords: String,
+    themes: Vec<Theme>,
+    new_url: String,
+    xtream_server: String,
+    xtream_username: String,
+    xtream_password: String,
 }
 
 impl SettingsView {
-    pub fn new(groups: Vec<Group>, m3u_path: Option<String>) -> Self {
-        Self { groups, m3u_path }
+    pub fn new() -> Self {
+        let config = Config::load();
+        Self {
+            tuner_groups: config
+                .server
+                .hdhomerun
+                .groups
+                .iter()
+                .map(|g| group_name(g))
+                .collect::<Vec<_>>()
+                .join(", "),
+            new_pin: String::new(),
+            lock_keywords: config.parental.keywords.join(", "),
+            config,
+            themes: theme::available(),
+            player: UserState::load().player,
+            groups: store::groups(),
+            last_diff: None,
+            server_error: None,
+            new_url: String::new(),
+            xtream_server: String::new(),
+            xtream_username: String::new(),
+            xtream_password: String::new(),
+        }
     }
 
-    pub fn refresh_playlist(&mut self) {
+    pub fn select_file(&mut self) {
         let filepath = FileDialog::new()
-            .set_title("Choose a M3U file...")
-            .pick_file()
-            .map(|path| path.to_string_lossy().into_owned());
-
-        self.m3u_path = filepath.clone();
-        self.groups = match filepath {
-            Some(path) => {
-                let groups = parse_m3u(&path).unwrap_or_default();
-                println!("Loaded {} groups from {}", groups.len(), path);
-                groups
-            }
-            None => vec![],
+            .set_title(t!("choose-m3u-file"))
+            .pick_file();
+
+        if let Some(path) = filepath {
+            let name = path
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let path = path.to_string_lossy().into_owned();
+            self.add_source(PlaylistSource::new(name, SourceKind::File { path }));
+        }
+    }
+
+    fn add_source(&mut self, mut source: PlaylistSource) {
+        // A source renamed keeps its identifier, which a new one must not take
+        let id = source.id.clone();
+        let mut n = 1;
+        while self.config.sources.iter().any(|s| s.id == source.id) {
+            n += 1;
+            source.id = format!("{} {}", id, n);
+        }
+        self.config.sources.push(source);
+        self.save_config();
+        self.refresh_playlist(false);
+    }
+
+    pub fn refresh_playlist(&self, download: bool) {
+        reload_in_background(self.config.clone(), download);
+    }
+
+    fn save_config(&self) {
+        if let Err(e) = self.config.save() {
+            eprintln!("Failed to save settings: {}", e);
+        }
+    }
+
+    fn create_source_row(&self, index: usize, source: &PlaylistSource) -> Element<ViewMessage> {
+        let kind = match source.kind {
+            SourceKind::File { .. } => t!("source-file"),
+            SourceKind::Url { .. } => t!("source-url"),
+            SourceKind::Xtream { .. } => t!("source-xtream"),
+        };
+        let refresh = source
+            .refresh_hours
+            .map(|h| h.to_string())
+            .unwrap_or_default();
+
+        Row::new()
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .push(checkbox("", source.enabled).on_toggle(move |b| {
+                ViewMessage::SettingsViewMessage(Message::SourceToggled(index, b))
+            }))
+            .push(
+                text_input(&t!("source-name"), &source.name)
+                    .on_input(move |s| {
+                        ViewMessage::SettingsViewMessage(Message::SourceNameChanged(index, s))
+                    })
+                    .width(200),
+            )
+            .push(text(kind).width(60))
+            .push(text(source.location()).width(Length::Fill))
+            .push(
+                text_input(&t!("source-refresh-hours"), &refresh)
+                    .on_input(move |s| {
+                        ViewMessage::SettingsViewMessage(Message::SourceRefreshChanged(index, s))
+                    })
+                    .width(80),
+            )
+            .push(
+                text_input(
+                    &t!("source-epg"),
+                    source.epg_url.as_deref().unwrap_or_default(),
+                )
+                .on_input(move |s| {
+                    ViewMessage::SettingsViewMessage(Message::SourceEpgChanged(index, s))
+                })
+                .width(150),
+            )
+            .push(
+                checkbox(t!("source-on-startup"), source.refresh_on_startup).on_toggle(move |b| {
+                    ViewMessage::SettingsViewMessage(Message::SourceStartupToggled(index, b))
+                }),
+            )
+            .push(
+                button(text(t!("remove"))).on_press(ViewMessage::SettingsViewMessage(
+                    Message::RemoveSource(index),
+                )),
+            )
+            .into()
+    }
+
+    fn create_retry_row(&self) -> Element<ViewMessage> {
+        let retry = &self.config.retry;
+
+        Row::new()
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .push(
+                checkbox(t!("retry-failover"), retry.failover)
+                    .on_toggle(|b| ViewMessage::SettingsViewMessage(Message::FailoverToggled(b))),
+            )
+            .push(text(t!("retry-attempts")))
+            .push(
+                text_input("1", &retry.attempts.to_string())
+                    .on_input(|s| ViewMessage::SettingsViewMessage(Message::AttemptsChanged(s)))
+                    .width(60),
+            )
+            .push(text(t!("retry-timeout")))
+            .push(
+                text_input("15", &retry.timeout_secs.to_string())
+                    .on_input(|s| ViewMessage::SettingsViewMessage(Message::TimeoutChanged(s)))
+                    .width(60),
+            )
+            .into()
+    }
+
+    fn save_player(&self) {
+        if let Err(e) = UserState::update(|state| state.player = self.player.clone()) {
+            eprintln!("Failed to save the player settings: {}", e);
+        }
+    }
+
+    fn create_player_row(&self) -> Element<ViewMessage> {
+        let embedded = checkbox(t!("embedded-player"), self.config.embedded_player)
+            .on_toggle(|b| ViewMessage::SettingsViewMessage(Message::EmbeddedPlayerToggled(b)));
+        let volume = self
+            .player
+            .volume
+            .map(|v| v.to_string())
+            .unwrap_or_default();
+
+        Row::new()
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .push(embedded)
+            .push(text(t!("player-volume")))
+            .push(
+                text_input("100", &volume)
+                    .on_input(|s| ViewMessage::SettingsViewMessage(Message::VolumeChanged(s)))
+                    .width(60),
+            )
+            .push(
+                text_input(&t!("player-audio-languages"), &self.player.audio_languages).on_input(
+                    |s| ViewMessage::SettingsViewMessage(Message::AudioLanguagesChanged(s)),
+                ),
+            )
+            .push(
+                text_input(
+                    &t!("player-subtitle-languages"),
+                    &self.player.subtitle_languages,
+                )
+                .on_input(|s| {
+                    ViewMessage::SettingsViewMessage(Message::SubtitleLanguagesChanged(s))
+                }),
+            )
+            .into()
+    }
+
+    fn restart_server(&mut self) {
+        self.server_error = None;
+        if !self.config.server.enabled {
+            server::stop();
+        } else if let Err(e) = server::start(&self.config.server) {
+            self.server_error = Some(e.to_string());
+        }
+    }
+
+    fn create_server_row(&self) -> Element<ViewMessage> {
+        let config = &self.config.server;
+        let status = match &self.server_error {
+            Some(error) => t!("server-error", error = error),
+            None if config.enabled => t!(
+                "server-urls",
+                address = &config.address,
+                port = config.port.to_string()
+            ),
+            None => String::new(),
         };
+        let tuner = &config.hdhomerun;
+
+        Column::new()
+            .spacing(10)
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_y(Alignment::Center)
+                    .push(
+                        checkbox(t!("server-enabled"), config.enabled).on_toggle(|b| {
+                            ViewMessage::SettingsViewMessage(Message::ServerToggled(b))
+                        }),
+                    )
+                    .push(
+                        text_input("127.0.0.1", &config.address)
+                            .on_input(|s| {
+                                ViewMessage::SettingsViewMessage(Message::ServerAddressChanged(s))
+                            })
+                            .width(150),
+                    )
+                    .push(
+                        text_input("8555", &config.port.to_string())
+                            .on_input(|s| {
+                                ViewMessage::SettingsViewMessage(Message::ServerPortChanged(s))
+                            })
+                            .width(80),
+                    )
+                    .push(
+                        button(text(t!("server-restart")))
+                            .on_press(ViewMessage::SettingsViewMessage(Message::RestartServer)),
+                    ),
+            )
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_y(Alignment::Center)
+                    .push(
+                        checkbox(t!("hdhomerun-enabled"), tuner.enabled).on_toggle(|b| {
+                            ViewMessage::SettingsViewMessage(Message::HdHomeRunToggled(b))
+                        }),
+                    )
+                    .push(text(t!("hdhomerun-tuners")))
+                    .push(
+                        text_input("2", &tuner.tuner_count.to_string())
+                            .on_input(|s| {
+                                ViewMessage::SettingsViewMessage(Message::TunerCountChanged(s))
+                            })
+                            .width(60),
+                    )
+                    .push(
+                        text_input(&t!("hdhomerun-groups"), &self.tuner_groups).on_input(|s| {
+                            ViewMessage::SettingsViewMessage(Message::TunerGroupsChanged(s))
+                        }),
+                    )
+                    .push(
+                        checkbox(t!("hdhomerun-favorites-only"), tuner.favorites_only).on_toggle(
+                            |b| ViewMessage::SettingsViewMessage(Message::TunerFavoritesToggled(b)),
+                        ),
+                    ),
+            )
+            .push(
+                Row::new()
+                    .spacing(10)
+                    .align_y(Alignment::Center)
+                    .push(
+                        checkbox(t!("api-enabled"), config.api.enabled).on_toggle(|b| {
+                            ViewMessage::SettingsViewMessage(Message::ApiToggled(b))
+                        }),
+                    )
+                    .push(
+                        text_input(
+                            &t!("api-token"),
+                            config.api.token.as_deref().unwrap_or_default(),
+                        )
+                        .on_input(|s| ViewMessage::SettingsViewMessage(Message::ApiTokenChanged(s)))
+                        .secure(true)
+                        .width(250),
+                    ),
+            )
+            .push(text(status).size(14))
+            .into()
+    }
+
+    /// PIN and lock keywords, out of reach while the controls are locked
+    fn create_parental_row(&self) -> Element<ViewMessage> {
+        let parental = &self.config.parental;
+        if parental.is_restricted() {
+            return text(t!("parental-restricted")).size(14).into();
+        }
+        let status = if parental.pin_hash.is_some() {
+            t!("pin-set")
+        } else {
+            t!("pin-unset")
+        };
+
+        Row::new()
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .push(
+                text_input(&t!("new-pin"), &self.new_pin)
+                    .secure(true)
+                    .on_input(|s| ViewMessage::SettingsViewMessage(Message::PinChanged(s)))
+                    .on_submit(ViewMessage::SettingsViewMessage(Message::PinSubmitted))
+                    .width(150),
+            )
+            .push(
+                button(text(t!("save-pin")))
+                    .on_press(ViewMessage::SettingsViewMessage(Message::PinSubmitted)),
+            )
+            .push(text(status).size(14))
+            .push(
+                text_input(&t!("lock-keywords"), &self.lock_keywords).on_input(|s| {
+                    ViewMessage::SettingsViewMessage(Message::LockKeywordsChanged(s))
+                }),
+            )
+            .into()
+    }
+
+    fn create_add_url_row(&self) -> Element<ViewMessage> {
+        Row::new()
+            .spacing(10)
+            .push(
+                text_input(&t!("playlist-url"), &self.new_url)
+                    .on_input(|s| ViewMessage::SettingsViewMessage(Message::NewUrlChanged(s)))
+                    .on_submit(ViewMessage::SettingsViewMessage(Message::AddUrl)),
+            )
+            .push(
+                button(text(t!("add-url")))
+                    .on_press(ViewMessage::SettingsViewMessage(Message::AddUrl)),
+            )
+            .into()
+    }
+
+    fn create_add_xtream_row(&self) -> Element<ViewMessage> {
+        Row::new()
+            .spacing(10)
+            .push(
+                text_input(&t!("xtream-server"), &self.xtream_server).on_input(|s| {
+                    ViewMessage::SettingsViewMessage(Message::XtreamServerChanged(s))
+                }),
+            )
+            .push(
+                text_input(&t!("xtream-username"), &self.xtream_username).on_input(|s| {
+                    ViewMessage::SettingsViewMessage(Message::XtreamUsernameChanged(s))
+                }),
+            )
+            .push(
+                text_input(&t!("xtream-password"), &self.xtream_password)
+                    .secure(true)
+                    .on_input(|s| {
+                        ViewMessage::SettingsViewMessage(Message::XtreamPasswordChanged(s))
+                    }),
+            )
+            .push(
+                button(text(t!("add-xtream")))
+                    .on_press(ViewMessage::SettingsViewMessage(Message::AddXtream)),
+            )
+            .into()
+    }
+}
+
+impl Default for SettingsView {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl View for SettingsView {
-    fn update(&mut self, message: ViewMessage) -> Option<Box<dyn View>> {
+    fn update(&mut self, message: ViewMessage) -> Option<Navigation> {
         match message {
             ViewMessage::SettingsViewMessage(msg) => match msg {
                 Message::SelectFile => {
-                    self.refresh_playlist();
+                    self.select_file();
+                }
+                Message::ReloadSources => {
+                    self.refresh_playlist(true);
+                }
+                Message::CleanupSelected => {
+                    return Some(Navigation::Push(Box::new(CleanupView::new())));
+                }
+                Message::ExportFavorites => {
+                    let state = UserState::load();
+                    let groups = state.apply(&self.groups, false, true);
+                    if let Some(favorites) = state.favorites_group(&groups) {
+                        export_channels(&favorites.channels, &group_name(FAVORITES_GROUP));
+                    }
+                }
+                Message::PrefixGroupsToggled(prefix) => {
+                    self.config.prefix_groups = prefix;
+                    self.save_config();
+                    self.refresh_playlist(false);
+                }
+                Message::MergeDuplicatesToggled(merge) => {
+                    self.config.merge_duplicates = merge;
+                    self.save_config();
+                    self.refresh_playlist(false);
+                }
+                Message::EmbeddedPlayerToggled(embedded) => {
+                    self.config.embedded_player = embedded;
+                    self.save_config();
+                }
+                Message::PreferredQualitySelected(OrAutomatic(quality)) => {
+                    self.config.preferred_quality = quality.map(|q| q.to_string());
+                    self.save_config();
+                    self.refresh_playlist(false);
+                }
+                Message::LanguageSelected(OrAutomatic(language)) => {
+                    self.config.language = language;
+                    self.save_config();
+                    i18n::set_language(language);
+                }
+                Message::ThemeSelected(OrAutomatic(theme)) => {
+                    self.config.theme = theme.map(|t| t.to_string());
+                    self.save_config();
+                    theme::set(self.config.theme.clone());
+                }
+                Message::FailoverToggled(failover) => {
+                    self.config.retry.failover = failover;
+                    self.save_config();
+                }
+                Message::AttemptsChanged(attempts) => {
+                    if let Ok(attempts) = attempts.parse() {
+                        self.config.retry.attempts = attempts;
+                        self.save_config();
+                    }
+                }
+                Message::TimeoutChanged(timeout) => {
+                    if let Ok(timeout) = timeout.parse() {
+                        self.config.retry.timeout_secs = timeout;
+                        self.save_config();
+                    }
+                }
+                Message::VolumeChanged(volume) => {
+                    if volume.is_empty() {
+                        self.player.volume = None;
+                    } else if let Ok(volume) = volume.parse() {
+                        self.player.volume = Some(volume);
+                    }
+                    self.save_player();
+                }
+                Message::AudioLanguagesChanged(languages) => {
+                    self.player.audio_languages = languages;
+                    self.save_player();
+                }
+                Message::SubtitleLanguagesChanged(languages) => {
+                    self.player.subtitle_languages = languages;
+                    self.save_player();
+                }
+                Message::ServerToggled(enabled) => {
+                    self.config.server.enabled = enabled;
+                    self.save_config();
+                    self.restart_server();
+                }
+                Message::ServerAddressChanged(address) => {
+                    self.config.server.address = address;
+                    self.save_config();
+                }
+                Message::ServerPortChanged(port) => {
+                    if let Ok(port) = port.parse() {
+                        self.config.server.port = port;
+                        self.save_config();
+                    }
+                }
+                Message::RestartServer => {
+                    self.restart_server();
+                }
+                Message::HdHomeRunToggled(enabled) => {
+                    self.config.server.hdhomerun.enabled = enabled;
+                    self.save_config();
+                    self.restart_server();
+                }
+                Message::TunerCountChanged(count) => {
+                    if let Ok(count) = count.parse() {
+                        self.config.server.hdhomerun.tuner_count = count;
+                        self.save_config();
+                    }
+                }
+                Message::TunerGroupsChanged(groups) => {
+                    self.config.server.hdhomerun.groups = groups
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|g| !g.is_empty())
+                        .map(|g| {
+                            if g == group_name(FAVORITES_GROUP) {
+                                FAVORITES_GROUP.to_string()
+                            } else {
+                                g.to_string()
+                            }
+                        })
+                        .collect();
+                    self.tuner_groups = groups;
+                    self.save_config();
+                }
+                Message::TunerFavoritesToggled(favorites_only) => {
+                    self.config.server.hdhomerun.favorites_only = favorites_only;
+                    self.save_config();
+                }
+                Message::ApiToggled(enabled) => {
+                    self.config.server.api.enabled = enabled;
+                    self.save_config();
+                    self.restart_server();
+                }
+                Message::ApiTokenChanged(token) => {
+                    self.config.server.api.token = (!token.is_empty()).then_some(token);
+                    self.save_config();
+                }
+                Message::PinChanged(pin) => {
+                    self.new_pin = pin;
+                }
+                Message::PinSubmitted => {
+                    let pin = std::mem::take(&mut self.new_pin);
+                    match self.config.parental.set_pin(&pin) {
+                        Ok(()) => self.save_config(),
+                        Err(e) => eprintln!("Failed to set the parental PIN: {}", e),
+                    }
+                }
+                Message::LockKeywordsChanged(keywords) => {
+                    self.config.parental.keywords = keywords
+                        .split(',')
+                        .map(|k| k.trim().to_string())
+                        .filter(|k| !k.is_empty())
+                        .collect();
+                    self.lock_keywords = keywords;
+                    self.save_config();
+                }
+                Message::SourceNameChanged(index, name) => {
+                    self.config.sources[index].name = name;
+                    self.save_config();
+                }
+                Message::SourceToggled(index, enabled) => {
+                    self.config.sources[index].enabled = enabled;
+                    self.save_config();
+                    self.refresh_playlist(false);
+                }
+                Message::SourceRefreshChanged(index, hours) => {
+                    if hours.is_empty() {
+                        self.config.sources[index].refresh_hours = None;
+                    } else if let Ok(hours) = hours.parse() {
+                        self.config.sources[index].refresh_hours = Some(hours);
+                    }
+                    self.save_config();
+                }
+                Message::SourceEpgChanged(index, url) => {
+                    self.config.sources[index].epg_url = (!url.is_empty()).then_some(url);
+                    self.save_config();
+                }
+                Message::SourceStartupToggled(index, on_startup) => {
+                    self.config.sources[index].refresh_on_startup = on_startup;
+                    self.save_config();
+                }
+                Message::RemoveSource(index) => {
+                    self.config.sources.remove(index);
+                    self.save_config();
+                    self.refresh_playlist(false);
+                }
+                Message::NewUrlChanged(url) => {
+                    self.new_url = url;
+                }
+                Message::AddUrl => {
+                    if !self.new_url.is_empty() {
+                        let url = std::mem::take(&mut self.new_url);
+                        let name = url::Url::parse(&url)
+                            .ok()
+                            .and_then(|u| u.host_str().map(|h| h.to_string()))
+                            .unwrap_or_else(|| url.clone());
+                        self.add_source(PlaylistSource::new(name, SourceKind::Url { url }));
+                    }
+                }
+                Message::XtreamServerChanged(server) => {
+                    self.xtream_server = server;
+                }
+                Message::XtreamUsernameChanged(username) => {
+                    self.xtream_username = username;
+                }
+                Message::XtreamPasswordChanged(password) => {
+                    self.xtream_password = password;
+                }
+                Message::AddXtream => {
+                    if !self.xtream_server.is_empty() {
+                        let kind = SourceKind::Xtream {
+                            server: std::mem::take(&mut self.xtream_server),
+                            username: std::mem::take(&mut self.xtream_username),
+                            password: std::mem::take(&mut self.xtream_password),
+                        };
+                        let name = format!("Xtream {}", self.config.sources.len() + 1);
+                        self.add_source(PlaylistSource::new(name, kind));
+                    }
                 }
                 Message::BackToGroups => {
-                    return Some(Box::new(GroupView::new(
-                        self.groups.clone(),
-                        self.m3u_path.clone(),
-                    )));
+                    return Some(Navigation::Back);
                 }
             },
+            ViewMessage::Navigate(Action::Back) => {
+                return self.update(ViewMessage::SettingsViewMessage(Message::BackToGroups));
+            }
+            ViewMessage::PlaylistRefreshed(groups, diff) => {
+                self.groups = groups;
+                self.last_diff = Some(diff.summary());
+            }
             _ => {}
         }
         None
     }
 
     fn view(&self) -> Element<ViewMessage> {
-        let file_picker = button("Sélectionner un fichier M3U")
+        let file_picker = button(text(t!("add-m3u-file")))
             .on_press(ViewMessage::SettingsViewMessage(Message::SelectFile))
             .padding(10);
 
-        let back_button = button("Groupes")
+        let reload_button = button(text(t!("reload-playlists")))
+            .on_press(ViewMessage::SettingsViewMessage(Message::ReloadSources))
+            .padding(10);
+
+        let merge_duplicates = checkbox(t!("merge-duplicates"), self.config.merge_duplicates)
+            .on_toggle(|b| ViewMessage::SettingsViewMessage(Message::MergeDuplicatesToggled(b)));
+
+        let qualities: Vec<OrAutomatic<&'static str>> = std::iter::once(None)
+            .chain(QUALITIES.iter().copied().map(Some))
+            .map(OrAutomatic)
+            .collect();
+        let preferred_quality = self
+            .config
+            .preferred_quality
+            .as_deref()
+            .and_then(|q| QUALITIES.iter().find(|quality| **quality == q).copied());
+        let preferred_quality = Row::new()
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .push(text(t!("preferred-quality")))
+            .push(pick_list(
+                qualities,
+                Some(OrAutomatic(preferred_quality)),
+                |q| ViewMessage::SettingsViewMessage(Message::PreferredQualitySelected(q)),
+            ));
+
+        let languages: Vec<OrAutomatic<Language>> = std::iter::once(None)
+            .chain(Language::ALL.into_iter().map(Some))
+            .map(OrAutomatic)
+            .collect();
+        let language = Row::new()
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .push(text(t!("language")))
+            .push(pick_list(
+                languages,
+                Some(OrAutomatic(self.config.language)),
+                |l| ViewMessage::SettingsViewMessage(Message::LanguageSelected(l)),
+            ));
+
+        let themes: Vec<OrAutomatic<Theme>> = std::iter::once(None)
+            .chain(self.themes.iter().cloned().map(Some))
+            .map(OrAutomatic)
+            .collect();
+        let selected_theme = (!theme::follows_system()).then(theme::current);
+        let theme = Row::new()
+            .spacing(10)
+            .align_y(Alignment::Center)
+            .push(text(t!("theme")))
+            .push(pick_list(themes, Some(OrAutomatic(selected_theme)), |t| {
+                ViewMessage::SettingsViewMessage(Message::ThemeSelected(t))
+            }));
+
+        let cleanup_button = button(text(t!("cleanup")))
+            .on_press(ViewMessage::SettingsViewMessage(Message::CleanupSelected))
+            .padding(10);
+
+        let export_button = button(text(t!("export-favorites")))
+            .on_press(ViewMessage::SettingsViewMessage(Message::ExportFavorites))
+            .padding(10);
+
+        let back_button = button(text(t!("groups")))
             .on_press(ViewMessage::SettingsViewMessage(Message::BackToGroups))
             .padding(10);
 
-        let m3u_path = match &self.m3u_path {
-            Some(path) => text(path).size(16),
-            None => text("Aucun fichier M3U sélectionné").size(16),
+        let sources: Element<ViewMessage> = if self.config.sources.is_empty() {
+            text(t!("no-sources")).size(16).into()
+        } else {
+            self.config
+                .sources
+                .iter()
+                .enumerate()
+                .fold(Column::new().spacing(10), |column, (index, source)| {
+                    column.push(self.create_source_row(index, source))
+                })
+                .into()
         };
-        let data = text(format!("{} groupes chargés", self.groups.len())).size(16);
 
-        Container::new(
+        let prefix_groups = checkbox(t!("prefix-groups"), self.config.prefix_groups)
+            .on_toggle(|b| ViewMessage::SettingsViewMessage(Message::PrefixGroupsToggled(b)));
+
+        let data = text(t!("groups-loaded", count = self.groups.len())).size(16);
+        let last_diff = text(self.last_diff.clone().unwrap_or_default()).size(16);
+
+        Container::new(scrollable(
             Column::new()
                 .push(Space::with_height(20))
+                .push(sources)
                 .push(file_picker)
-                .push(Space::with_height(10))
-                .push(m3u_path)
-                .push(Space::with_height(10))
+                .push(self.create_add_url_row())
+                .push(self.create_add_xtream_row())
+                .push(prefix_groups)
+                .push(merge_duplicates)
+                .push(preferred_quality)
+                .push(language)
+                .push(theme)
+                .push(self.create_retry_row())
+                .push(self.create_player_row())
+                .push(self.create_server_row())
+                .push(self.create_parental_row())
+                .push(cleanup_button)
+                .push(reload_button)
                 .push(data)
+                .push(last_diff)
+                .push(export_button)
                 .push(Space::with_height(20))
                 .push(back_button)
                 .align_x(Horizontal::Center)
                 .spacing(20)
                 .padding(20),
-        )
+        ))
         .center_x(Length::Fill)
         .center_y(Length::Fill)
         .into()
     }
+
+    fn resume(&mut self) {
+        self.groups = store::groups();
+    }
 }
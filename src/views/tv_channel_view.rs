@@ -0,0 +1,152 @@
+use super::iced_utils::{create_tiles, move_focus, Tile, GRID_SCROLLABLE, OVERSCAN_PADDING};
+use super::{Navigation, View, ViewMessage};
+use crate::i18n::group_name;
+use crate::keymap::Action;
+use crate::logos::logo;
+use crate::m3u::{Channel, Group};
+use crate::mpv::play_channel;
+use crate::state::{UserState, FAVORITES_GROUP, RECENT_GROUP};
+use crate::store;
+use crate::t;
+use iced::widget::image::Handle;
+use iced::widget::{button, scrollable, text, Column, Container, Row};
+use iced::{Alignment, Element, Length};
+
+/// Channels of a group shown as large tiles with their logos
+pub struct TvChannelView {
+    group_name: String,
+    state: UserState,
+    channels: Vec<Channel>,
+    /// Logos of the channels, resolved once they are in the cache
+    logos: Vec<Option<Handle>>,
+    focused: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    BackToGroups,
+    ChannelSelected(usize),
+}
+
+impl TvChannelView {
+    pub fn new(group: Group) -> Self {
+        let mut view = Self {
+            group_name: group.name,
+            state: UserState::load(),
+            channels: group.channels,
+            logos: Vec::new(),
+            focused: None,
+        };
+        view.load_logos();
+        view
+    }
+
+    /// Resolves the logos still missing, the others keep their handle
+    fn load_logos(&mut self) {
+        self.logos.resize(self.channels.len(), None);
+        for (handle, channel) in self.logos.iter_mut().zip(&self.channels) {
+            if handle.is_none() {
+                *handle = channel.logo_url.as_deref().and_then(logo);
+            }
+        }
+    }
+
+    /// Reads the channels of the group again from the playlist and the user
+    /// state
+    fn reload(&mut self) {
+        self.state = UserState::load();
+        let edited_groups = self.state.apply(&store::groups(), false, false);
+        let group = match self.group_name.as_str() {
+            FAVORITES_GROUP => self.state.favorites_group(&edited_groups),
+            RECENT_GROUP => self.state.recent_group(&edited_groups),
+            name => edited_groups.into_iter().find(|g| g.name == name),
+        };
+        self.channels = group.map(|g| g.channels).unwrap_or_default();
+        self.logos.clear();
+        self.load_logos();
+    }
+
+    fn on_press(index: usize) -> ViewMessage {
+        ViewMessage::TvChannelViewMessage(Message::ChannelSelected(index))
+    }
+}
+
+impl View for TvChannelView {
+    fn update(&mut self, message: ViewMessage) -> Option<Navigation> {
+        match message {
+            ViewMessage::TvChannelViewMessage(msg) => match msg {
+                Message::BackToGroups => {
+                    return Some(Navigation::Back);
+                }
+                Message::ChannelSelected(index) => {
+                    self.focused = Some(index);
+                    if let Some(channel) = self.channels.get(index) {
+                        play_channel(channel.clone());
+                    }
+                }
+            },
+            ViewMessage::Navigate(Action::Activate) => {
+                return self.update(Self::on_press(self.focused.unwrap_or_default()));
+            }
+            ViewMessage::Navigate(Action::Back) => {
+                return self.update(ViewMessage::TvChannelViewMessage(Message::BackToGroups));
+            }
+            ViewMessage::Navigate(action) => {
+                self.focused = move_focus(self.focused, self.channels.len(), action);
+            }
+            ViewMessage::PlaylistRefreshed(..) => {
+                self.reload();
+                self.focused = None;
+            }
+            ViewMessage::LogoLoaded => self.load_logos(),
+            _ => {}
+        }
+        None
+    }
+
+    fn view(&self) -> Element<ViewMessage> {
+        let tiles: Vec<Tile> = self
+            .channels
+            .iter()
+            .zip(&self.logos)
+            .map(|(channel, logo)| Tile {
+                label: if self.state.is_favorite(channel) {
+                    format!("★ {}", channel.name)
+                } else {
+                    channel.name.clone()
+                },
+                logo: logo.clone(),
+            })
+            .collect();
+
+        let header = Row::new()
+            .spacing(30)
+            .align_y(Alignment::Center)
+            .push(
+                button(text(t!("back")).size(24))
+                    .on_press(ViewMessage::TvChannelViewMessage(Message::BackToGroups))
+                    .padding(15),
+            )
+            .push(text(group_name(&self.group_name)).size(40));
+
+        Container::new(
+            Column::new().spacing(30).push(header).push(
+                scrollable(create_tiles(tiles, Self::on_press, self.focused))
+                    .id(GRID_SCROLLABLE.clone())
+                    .on_scroll(|viewport| ViewMessage::GridScrolled(viewport.absolute_offset()))
+                    .height(Length::Fill)
+                    .width(Length::Fill),
+            ),
+        )
+        .padding(OVERSCAN_PADDING)
+        .into()
+    }
+
+    fn resume(&mut self) {
+        self.reload();
+    }
+
+    fn grid_focus(&self) -> Option<(usize, usize)> {
+        Some((self.focused?, self.channels.len()))
+    }
+}
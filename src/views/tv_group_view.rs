@@ -0,0 +1,127 @@
+use super::iced_utils::{create_tiles, move_focus, Tile, GRID_SCROLLABLE, OVERSCAN_PADDING};
+use super::{Navigation, TvChannelView, TvSettingsView, View, ViewMessage};
+use crate::i18n::group_name;
+use crate::keymap::Action;
+use crate::m3u::Group;
+use crate::state::UserState;
+use crate::store;
+use crate::t;
+use iced::widget::{scrollable, text, Column, Container};
+use iced::{Element, Length};
+use std::sync::Arc;
+
+/// Groups shown as large tiles, the settings tile closing the grid
+pub struct TvGroupView {
+    groups: Arc<Vec<Group>>,
+    shown_groups: Vec<Group>,
+    focused: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    TileSelected(usize),
+}
+
+impl TvGroupView {
+    pub fn new() -> Self {
+        let mut view = Self {
+            groups: store::groups(),
+            shown_groups: Vec::new(),
+            focused: None,
+        };
+        view.apply_edits();
+        view
+    }
+
+    fn apply_edits(&mut self) {
+        let state = UserState::load();
+        let edited_groups = state.apply(&self.groups, false, false);
+        self.shown_groups = state
+            .favorites_group(&edited_groups)
+            .into_iter()
+            .chain(state.recent_group(&edited_groups))
+            .chain(edited_groups)
+            .collect();
+    }
+
+    fn tile_count(&self) -> usize {
+        self.shown_groups.len() + 1
+    }
+
+    fn on_press(index: usize) -> ViewMessage {
+        ViewMessage::TvGroupViewMessage(Message::TileSelected(index))
+    }
+}
+
+impl Default for TvGroupView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl View for TvGroupView {
+    fn update(&mut self, message: ViewMessage) -> Option<Navigation> {
+        match message {
+            ViewMessage::TvGroupViewMessage(Message::TileSelected(index)) => {
+                let view: Box<dyn View> = match self.shown_groups.get(index) {
+                    Some(group) => Box::new(TvChannelView::new(group.clone())),
+                    None => Box::new(TvSettingsView::new()),
+                };
+                return Some(Navigation::Push(view));
+            }
+            ViewMessage::Navigate(Action::Activate) => {
+                return self.update(Self::on_press(self.focused.unwrap_or_default()));
+            }
+            ViewMessage::Navigate(Action::Back) => {}
+            ViewMessage::Navigate(action) => {
+                self.focused = move_focus(self.focused, self.tile_count(), action);
+            }
+            ViewMessage::PlaylistRefreshed(groups, _) => {
+                self.groups = groups;
+                self.focused = None;
+                self.apply_edits();
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn view(&self) -> Element<ViewMessage> {
+        let tiles: Vec<Tile> = self
+            .shown_groups
+            .iter()
+            .map(|group| {
+                Tile::new(format!(
+                    "{}\n{}",
+                    group_name(&group.name),
+                    t!("channel-count", count = group.channels.len())
+                ))
+            })
+            .chain(std::iter::once(Tile::new(t!("settings"))))
+            .collect();
+
+        Container::new(
+            Column::new()
+                .spacing(30)
+                .push(text("Rustream").size(40))
+                .push(
+                    scrollable(create_tiles(tiles, Self::on_press, self.focused))
+                        .id(GRID_SCROLLABLE.clone())
+                        .on_scroll(|viewport| ViewMessage::GridScrolled(viewport.absolute_offset()))
+                        .height(Length::Fill)
+                        .width(Length::Fill),
+                ),
+        )
+        .padding(OVERSCAN_PADDING)
+        .into()
+    }
+
+    fn resume(&mut self) {
+        self.groups = store::groups();
+        self.apply_edits();
+    }
+
+    fn grid_focus(&self) -> Option<(usize, usize)> {
+        Some((self.focused?, self.tile_count()))
+    }
+}
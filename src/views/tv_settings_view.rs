@@ -0,0 +1,152 @@
+use super::iced_utils::{
+    create_tiles, move_focus, OrAutomatic, Tile, GRID_SCROLLABLE, OVERSCAN_PADDING,
+};
+use super::{home, Navigation, ProfileView, SettingsView, View, ViewMessage};
+use crate::config::Config;
+use crate::duplicates::QUALITIES;
+use crate::keymap::Action;
+use crate::playlist::reload_in_background;
+use crate::t;
+use iced::widget::{scrollable, text, Column, Container};
+use iced::{Element, Length};
+
+const TILES: usize = 6;
+
+/// Settings reachable with a remote, the others are left to the desktop view
+pub struct TvSettingsView {
+    config: Config,
+    last_diff: Option<String>,
+    focused: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    DesktopModeSelected,
+    ReloadSources,
+    QualityCycled,
+    AdvancedSelected,
+    ProfilesSelected,
+    BackToGroups,
+}
+
+impl TvSettingsView {
+    pub fn new() -> Self {
+        Self {
+            config: Config::load(),
+            last_diff: None,
+            focused: None,
+        }
+    }
+
+    fn save_config(&self) {
+        if let Err(e) = self.config.save() {
+            eprintln!("Failed to save settings: {}", e);
+        }
+    }
+
+    fn reload(&self, download: bool) {
+        reload_in_background(self.config.clone(), download);
+    }
+
+    fn on_press(index: usize) -> ViewMessage {
+        let message = match index {
+            0 => Message::DesktopModeSelected,
+            1 => Message::ReloadSources,
+            2 => Message::QualityCycled,
+            3 => Message::AdvancedSelected,
+            4 => Message::ProfilesSelected,
+            _ => Message::BackToGroups,
+        };
+        ViewMessage::TvSettingsViewMessage(message)
+    }
+}
+
+impl Default for TvSettingsView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl View for TvSettingsView {
+    fn update(&mut self, message: ViewMessage) -> Option<Navigation> {
+        match message {
+            ViewMessage::TvSettingsViewMessage(msg) => match msg {
+                Message::DesktopModeSelected => {
+                    self.config.tv_mode = false;
+                    self.save_config();
+                    return Some(Navigation::Reset(home()));
+                }
+                Message::ReloadSources => {
+                    self.reload(true);
+                }
+                Message::QualityCycled => {
+                    // Automatic, then from the best quality to the worst
+                    let next = match self.config.preferred_quality.as_deref() {
+                        None => QUALITIES.first(),
+                        Some(quality) => QUALITIES
+                            .iter()
+                            .position(|q| *q == quality)
+                            .and_then(|position| QUALITIES.get(position + 1)),
+                    };
+                    self.config.preferred_quality = next.map(|q| q.to_string());
+                    self.save_config();
+                    self.reload(false);
+                }
+                Message::AdvancedSelected => {
+                    return Some(Navigation::Push(Box::new(SettingsView::new())));
+                }
+                Message::ProfilesSelected => {
+                    return Some(Navigation::Push(Box::new(ProfileView::new())));
+                }
+                Message::BackToGroups => {
+                    return Some(Navigation::Back);
+                }
+            },
+            ViewMessage::Navigate(Action::Activate) => {
+                return self.update(Self::on_press(self.focused.unwrap_or_default()));
+            }
+            ViewMessage::Navigate(Action::Back) => {
+                return self.update(ViewMessage::TvSettingsViewMessage(Message::BackToGroups));
+            }
+            ViewMessage::Navigate(action) => {
+                self.focused = move_focus(self.focused, TILES, action);
+            }
+            ViewMessage::PlaylistRefreshed(_, diff) => {
+                self.last_diff = Some(diff.summary());
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn view(&self) -> Element<ViewMessage> {
+        let quality = OrAutomatic(self.config.preferred_quality.as_deref());
+        let tiles = vec![
+            Tile::new(t!("desktop-mode")),
+            Tile::new(t!("reload-playlists")),
+            Tile::new(format!("{}\n{}", t!("preferred-quality"), quality)),
+            Tile::new(t!("advanced-settings")),
+            Tile::new(t!("profiles")),
+            Tile::new(t!("back")),
+        ];
+
+        Container::new(
+            Column::new()
+                .spacing(30)
+                .push(text(t!("settings")).size(40))
+                .push(
+                    scrollable(create_tiles(tiles, Self::on_press, self.focused))
+                        .id(GRID_SCROLLABLE.clone())
+                        .height(Length::Fill)
+                        .width(Length::Fill),
+                )
+                .push(text(self.last_diff.clone().unwrap_or_default()).size(24)),
+        )
+        .padding(OVERSCAN_PADDING)
+        .into()
+    }
+
+    fn grid_focus(&self) -> Option<(usize, usize)> {
+        Some((self.focused?, TILES))
+    }
+}